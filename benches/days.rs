@@ -0,0 +1,97 @@
+use aoc2023::*;
+
+use criterion::Criterion;
+use serde_json::{json, Value};
+
+use std::env;
+use std::fs;
+use std::hint::black_box;
+use std::path::{Path, PathBuf};
+
+/// Benchmarks a day's generator and each of its solvers against its puzzle input, as
+/// `dayN/parse` and `dayN/<solver>`.
+macro_rules! bench_day {
+    ($c:expr, $day:ident { $($name:literal => $solver:ident),* }) => {{
+        let path = concat!("input/2023/", stringify!($day), ".txt");
+        let input = fs::read_to_string(path).unwrap();
+        let parsed = $day::parse(&input).unwrap();
+        let mut group = $c.benchmark_group(stringify!($day));
+        group.bench_function("parse", |b| b.iter(|| $day::parse(black_box(&input))));
+        $(group.bench_function($name, |b| b.iter(|| $day::$solver(black_box(&parsed))));)*
+        group.finish();
+    }};
+}
+
+fn bench_days(c: &mut Criterion) {
+    bench_day!(c, day1 { "part1" => part1, "part2" => part2 });
+    bench_day!(c, day2 { "part1" => part1, "part2" => part2 });
+    bench_day!(c, day3 { "part1" => part1, "part2" => part2 });
+    bench_day!(c, day4 { "part1" => part1, "part2" => part2 });
+    bench_day!(c, day5 { "part1" => part1, "part2" => part2, "part2-inverse" => part2_inverse });
+    bench_day!(c, day6 { "part1" => part1, "part2" => part2 });
+    bench_day!(c, day7 { "part1" => part1, "part2" => part2 });
+    bench_day!(c, day8 { "part1" => part1, "part2-simplified" => part2_simplified, "part2-generic" => part2_generic });
+    bench_day!(c, day9 { "part1" => part1, "part2" => part2 });
+    bench_day!(c, day10 { "part1" => part1, "part2" => part2, "part2-shoelace" => part2_shoelace, "part2-squeeze" => part2_squeeze });
+    bench_day!(c, day11 { "part1" => part1, "part2" => part2 });
+    bench_day!(c, day12 { "part1" => part1, "part2" => part2 });
+    bench_day!(c, day13 { "part1" => part1, "part2" => part2 });
+    bench_day!(c, day14 { "part1" => part1, "part2" => part2 });
+    bench_day!(c, day15 { "part1" => part1, "part2" => part2 });
+}
+
+/// Where criterion keeps its results, following its own lookup order.
+fn criterion_home() -> PathBuf {
+    if let Some(home) = env::var_os("CRITERION_HOME") {
+        return home.into();
+    }
+    let target = env::var_os("CARGO_TARGET_DIR").map_or_else(|| "target".into(), PathBuf::from);
+    target.join("criterion")
+}
+
+fn point_estimate(path: &Path, statistic: &str) -> Option<f64> {
+    let estimates: Value = serde_json::from_str(&fs::read_to_string(path).ok()?).ok()?;
+    estimates[statistic]["point_estimate"].as_f64()
+}
+
+/// Collects the latest estimates of every benchmark into `summary.json`, one entry per
+/// benchmark with its mean, median and standard deviation in nanoseconds, and the relative
+/// change of the mean since the previous run, if criterion measured one.
+fn write_summary(home: &Path) -> std::io::Result<PathBuf> {
+    let mut entries = Vec::new();
+    for group in fs::read_dir(home)? {
+        let group = group?.path();
+        if !group.is_dir() {
+            continue;
+        }
+        for bench in fs::read_dir(&group)? {
+            let bench = bench?.path();
+            let estimates = bench.join("new").join("estimates.json");
+            let Some(mean) = point_estimate(&estimates, "mean") else {
+                continue;
+            };
+            let name = |path: &Path| path.file_name().unwrap().to_string_lossy().into_owned();
+            entries.push(json!({
+                "benchmark": format!("{}/{}", name(&group), name(&bench)),
+                "mean_ns": mean,
+                "median_ns": point_estimate(&estimates, "median"),
+                "std_dev_ns": point_estimate(&estimates, "std_dev"),
+                "change": point_estimate(&bench.join("change").join("estimates.json"), "mean"),
+            }));
+        }
+    }
+    entries.sort_by_key(|entry| entry["benchmark"].as_str().unwrap().to_string());
+    let path = home.join("summary.json");
+    fs::write(&path, serde_json::to_string_pretty(&entries)?)?;
+    Ok(path)
+}
+
+fn main() {
+    let mut c = Criterion::default().configure_from_args();
+    bench_days(&mut c);
+    c.final_summary();
+    match write_summary(&criterion_home()) {
+        Ok(path) => println!("Wrote summary to {}", path.display()),
+        Err(err) => eprintln!("Could not write summary: {}", err),
+    }
+}
@@ -0,0 +1,36 @@
+//! Draws a day10 maze, with the loop, the tiles inside it and the tiles outside it told apart.
+//!
+//! Usage: `cargo run --example day10_render [text|ppm|svg] [input] > out`
+
+use aoc2023::day10;
+
+use std::env;
+use std::fs;
+use std::io::Write;
+use std::process::exit;
+
+fn main() {
+    let mut args = env::args().skip(1);
+    let format = args.next().unwrap_or_else(|| "text".to_string());
+    let path = args
+        .next()
+        .unwrap_or_else(|| "input/2023/day10.txt".to_string());
+    let input = fs::read_to_string(&path).unwrap_or_else(|err| {
+        eprintln!("cannot read {}: {}", path, err);
+        exit(1);
+    });
+    let maze = day10::parse(&input).unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        exit(1);
+    });
+    let output = match format.as_str() {
+        "text" => day10::render(&maze, true).into_bytes(),
+        "ppm" => day10::to_ppm(&maze, 4),
+        "svg" => day10::to_svg(&maze).into_bytes(),
+        other => {
+            eprintln!("unknown format '{}', expected text, ppm or svg", other);
+            exit(2);
+        }
+    };
+    std::io::stdout().write_all(&output).unwrap();
+}
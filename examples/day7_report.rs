@@ -0,0 +1,39 @@
+//! Prints how each day7 hand ranked and why.
+//!
+//! Usage: `cargo run --example day7_report [part1|part2] [input]`
+
+use aoc2023::day7;
+use aoc2023::day7::RuleSet;
+
+use std::env;
+use std::fs;
+use std::process::exit;
+
+fn main() {
+    let mut args = env::args().skip(1);
+    let rules = match args.next().as_deref() {
+        None | Some("part1") => RuleSet::standard(),
+        Some("part2") => RuleSet::jokers(),
+        Some(other) => {
+            eprintln!("unknown rules '{}', expected part1 or part2", other);
+            exit(2);
+        }
+    };
+    let path = args
+        .next()
+        .unwrap_or_else(|| "input/2023/day7.txt".to_string());
+    let input = fs::read_to_string(&path).unwrap_or_else(|err| {
+        eprintln!("cannot read {}: {}", path, err);
+        exit(1);
+    });
+    let hands = day7::parse(&input).unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        exit(1);
+    });
+    let report = day7::report(&rules, &hands);
+    for row in &report {
+        println!("{}", row);
+    }
+    let total: u32 = report.iter().map(|row| row.winnings).sum();
+    println!("total winnings: {}", total);
+}
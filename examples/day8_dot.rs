@@ -0,0 +1,24 @@
+//! Prints a day8 network as a Graphviz graph, with each ghost's loop highlighted.
+//!
+//! Usage: `cargo run --example day8_dot [input] | dot -Tsvg > day8.svg`
+
+use aoc2023::day8;
+
+use std::env;
+use std::fs;
+use std::process::exit;
+
+fn main() {
+    let path = env::args()
+        .nth(1)
+        .unwrap_or_else(|| "input/2023/day8.txt".to_string());
+    let input = fs::read_to_string(&path).unwrap_or_else(|err| {
+        eprintln!("cannot read {}: {}", path, err);
+        exit(1);
+    });
+    let network = day8::parse(&input).unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        exit(1);
+    });
+    print!("{}", day8::to_dot(&network));
+}
@@ -2,12 +2,16 @@ use aoc_runner_derive::aoc;
 use aoc_runner_derive::aoc_generator;
 use maplit::hashmap;
 
-#[aoc_generator(day1, part1)]
-fn input_part1(input: &str) -> Vec<Vec<u32>> {
-    input
-        .lines()
-        .map(|l| l.chars().filter_map(|c| c.to_digit(10)).collect::<Vec<_>>())
-        .collect()
+use crate::parse::{lines, ParseError};
+
+const DAY: u32 = 1;
+
+/// A line of the calibration document, with its first and last digit read in both ways.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CalibrationLine {
+    pub line: usize,
+    pub digits: Option<(u32, u32)>,
+    pub spelled: (u32, u32),
 }
 
 fn rev_inits(line: &str) -> impl Iterator<Item = &str> {
@@ -18,7 +22,7 @@ fn tails(line: &str) -> impl Iterator<Item = &str> {
     (0..line.len()).map(|start| &line[start..])
 }
 
-fn find_first_digit(line: &str) -> u32 {
+fn find_first_digit(line: &str) -> Option<u32> {
     let numbers = hashmap! {
         "one" => 1u32,
         "two" => 2u32,
@@ -30,19 +34,17 @@ fn find_first_digit(line: &str) -> u32 {
         "eight" => 8u32,
         "nine" => 9u32,
     };
-    tails(line)
-        .find_map(|tail| {
-            tail.chars().next().unwrap().to_digit(10).or_else(|| {
-                numbers
-                    .keys()
-                    .find_map(|&key| tail.starts_with(key).then(|| numbers.get(key).unwrap()))
-                    .copied()
-            })
+    tails(line).find_map(|tail| {
+        tail.chars().next().unwrap().to_digit(10).or_else(|| {
+            numbers
+                .keys()
+                .find_map(|&key| tail.starts_with(key).then(|| numbers.get(key).unwrap()))
+                .copied()
         })
-        .unwrap()
+    })
 }
 
-fn find_last_digit(line: &str) -> u32 {
+fn find_last_digit(line: &str) -> Option<u32> {
     let numbers = hashmap! {
         "one" => 1u32,
         "two" => 2u32,
@@ -54,42 +56,58 @@ fn find_last_digit(line: &str) -> u32 {
         "eight" => 8u32,
         "nine" => 9u32,
     };
-    rev_inits(line)
-        .find_map(|init| {
-            init.chars().last().unwrap().to_digit(10).or_else(|| {
-                numbers
-                    .keys()
-                    .find_map(|&key| init.ends_with(key).then(|| numbers.get(key).unwrap()))
-                    .copied()
-            })
+    rev_inits(line).find_map(|init| {
+        init.chars().last().unwrap().to_digit(10).or_else(|| {
+            numbers
+                .keys()
+                .find_map(|&key| init.ends_with(key).then(|| numbers.get(key).unwrap()))
+                .copied()
         })
-        .unwrap()
+    })
 }
 
-#[aoc_generator(day1, part2)]
-fn input_part2(input: &str) -> Vec<Vec<u32>> {
-    input
-        .lines()
+/// Reads each line's first and last digit, both as written and with spelled-out digits.
+#[aoc_generator(day1)]
+pub fn parse(input: &str) -> Result<Vec<CalibrationLine>, ParseError> {
+    lines(DAY, input)
         .map(|line| {
-            let first = find_first_digit(line);
-            let last = find_last_digit(line);
-            vec![first, last]
+            if let Some((x, c)) = line.text.chars().enumerate().find(|(_, c)| !c.is_ascii()) {
+                return Err(line.unknown_char(x, c, "character"));
+            }
+            let first = line.text.chars().find_map(|c| c.to_digit(10));
+            let last = line.text.chars().rev().find_map(|c| c.to_digit(10));
+            match (find_first_digit(line.text), find_last_digit(line.text)) {
+                (Some(first_spelled), Some(last_spelled)) => Ok(CalibrationLine {
+                    line: line.number,
+                    digits: first.zip(last),
+                    spelled: (first_spelled, last_spelled),
+                }),
+                _ => Err(line.error("no digit or spelled-out digit found")),
+            }
         })
         .collect()
 }
 
+/// Fails on a line without a numeric digit, which `parse` lets through as part 2 inputs may
+/// spell all their digits out.
 #[aoc(day1, part1)]
-fn part1(lines: &[Vec<u32>]) -> String {
+pub fn part1(lines: &[CalibrationLine]) -> Result<u32, ParseError> {
     lines
         .iter()
-        .map(|digits| digits.first().unwrap() * 10 + digits.last().unwrap())
-        .sum::<u32>()
-        .to_string()
+        .map(|line| match line.digits {
+            Some((first, last)) => Ok(first * 10 + last),
+            None => Err(ParseError::at_line(DAY, line.line, "no digit found")),
+        })
+        .sum()
 }
 
+/// Sums the calibration values, counting spelled-out digits as digits too.
 #[aoc(day1, part2)]
-fn part2(lines: &[Vec<u32>]) -> String {
-    part1(lines)
+pub fn part2(lines: &[CalibrationLine]) -> u32 {
+    lines
+        .iter()
+        .map(|line| line.spelled.0 * 10 + line.spelled.1)
+        .sum()
 }
 
 #[cfg(test)]
@@ -102,7 +120,7 @@ mod tests {
                      pqr3stu8vwx
                      a1b2c3d4e5f
                      treb7uchet";
-        assert_eq!(part1(&input_part1(input)), "142")
+        assert_eq!(part1(&parse(input).unwrap()), Ok(142))
     }
 
     #[test]
@@ -114,6 +132,17 @@ xtwone3four
 4nineeightseven2
 zoneight234
 7pqrstsixteen";
-        assert_eq!(part2(&input_part2(input)), "281")
+        assert_eq!(part2(&parse(input).unwrap()), 281)
+    }
+
+    #[test]
+    fn missing_digit() {
+        let err = parse("1abc2\npqrstu").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "day1 line 2 col 1: no digit or spelled-out digit found"
+        );
+        let err = part1(&parse("1abc2\npqrthreestu").unwrap()).unwrap_err();
+        assert_eq!(err.to_string(), "day1 line 2 col 1: no digit found");
     }
 }
@@ -1,169 +1,278 @@
 use aoc_runner_derive::aoc;
 use aoc_runner_derive::aoc_generator;
 
-use std::collections::HashMap;
-use std::collections::HashSet;
+use std::collections::VecDeque;
+use std::error::Error;
+use std::fmt;
+use std::fmt::Display;
+use std::fmt::Write;
 
-type Pos = (usize, usize);
-type Size = (usize, usize);
+use crate::grid::{Delta, Grid, DOWN, LEFT, RIGHT, UP};
+use crate::parse::{lines, ParseError};
+
+const DAY: u32 = 10;
+
+pub use crate::grid::Pos;
 
 struct Loc {
     pos: Pos,
     dir: Dir,
 }
 
-#[repr(u8)]
-#[derive(Clone, Copy)]
-enum Dir {
-    Right = 1,
-    Down = 4,
-    Left = 16,
-    Up = 64,
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Dir {
+    Right,
+    Down,
+    Left,
+    Up,
 }
 
-#[repr(u8)]
-#[derive(Clone, Copy)]
-enum Tile {
-    LeftRight = Dir::Left as u8 | Dir::Right as u8,
-    UpDown = Dir::Up as u8 | Dir::Down as u8,
-    UpRight = Dir::Up as u8 | Dir::Right as u8,
-    UpLeft = Dir::Up as u8 | Dir::Left as u8,
-    DownLeft = Dir::Down as u8 | Dir::Left as u8,
-    DownRight = Dir::Down as u8 | Dir::Right as u8,
-    Empty = 0,
+impl Dir {
+    pub const ALL: [Dir; 4] = [Dir::Up, Dir::Down, Dir::Left, Dir::Right];
+
+    /// The direction's bit in a tile, four bits away from the opposite direction's.
+    pub const fn bit(self) -> u8 {
+        match self {
+            Dir::Right => 1,
+            Dir::Down => 4,
+            Dir::Left => 16,
+            Dir::Up => 64,
+        }
+    }
+
+    pub fn opposite(self) -> Dir {
+        match self {
+            Dir::Right => Dir::Left,
+            Dir::Down => Dir::Up,
+            Dir::Left => Dir::Right,
+            Dir::Up => Dir::Down,
+        }
+    }
+
+    pub fn delta(self) -> Delta {
+        match self {
+            Dir::Up => UP,
+            Dir::Down => DOWN,
+            Dir::Left => LEFT,
+            Dir::Right => RIGHT,
+        }
+    }
 }
 
-fn parse_tile(c: char) -> Option<Tile> {
-    match c {
-        '-' => Some(Tile::LeftRight),
-        '|' => Some(Tile::UpDown),
-        'L' => Some(Tile::UpRight),
-        'J' => Some(Tile::UpLeft),
-        '7' => Some(Tile::DownLeft),
-        'F' => Some(Tile::DownRight),
-        '.' => Some(Tile::Empty),
-        'S' => None,
-        _ => panic!(),
+impl TryFrom<u8> for Dir {
+    type Error = TileError;
+
+    fn try_from(bits: u8) -> Result<Dir, TileError> {
+        Dir::ALL
+            .into_iter()
+            .find(|dir| dir.bit() == bits)
+            .ok_or(TileError::Bits(bits))
     }
 }
 
-fn just_dir(tile: Tile, dir: Dir) -> u8 {
-    (tile as u8) & (dir as u8)
+/// The pipe on a tile, as the set of directions it connects.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Tile(u8);
+
+impl Tile {
+    pub const EMPTY: Tile = Tile(0);
+    pub const LEFT_RIGHT: Tile = Tile(Dir::Left.bit() | Dir::Right.bit());
+    pub const UP_DOWN: Tile = Tile(Dir::Up.bit() | Dir::Down.bit());
+    pub const UP_RIGHT: Tile = Tile(Dir::Up.bit() | Dir::Right.bit());
+    pub const UP_LEFT: Tile = Tile(Dir::Up.bit() | Dir::Left.bit());
+    pub const DOWN_LEFT: Tile = Tile(Dir::Down.bit() | Dir::Left.bit());
+    pub const DOWN_RIGHT: Tile = Tile(Dir::Down.bit() | Dir::Right.bit());
+
+    pub fn bits(self) -> u8 {
+        self.0
+    }
+
+    pub fn connects(self, dir: Dir) -> bool {
+        self.0 & dir.bit() != 0
+    }
+
+    pub fn dirs(self) -> impl Iterator<Item = Dir> {
+        Dir::ALL.into_iter().filter(move |&dir| self.connects(dir))
+    }
+
+    /// The box-drawing character for the pipe, or a space for no pipe.
+    pub fn box_char(self) -> char {
+        match self {
+            Tile::LEFT_RIGHT => '─',
+            Tile::UP_DOWN => '│',
+            Tile::UP_RIGHT => '└',
+            Tile::UP_LEFT => '┘',
+            Tile::DOWN_LEFT => '┐',
+            Tile::DOWN_RIGHT => '┌',
+            _ => ' ',
+        }
+    }
+
+    /// Which way a walker moving in `dir` leaves the tile, if the pipe lets it in at all.
+    pub fn turn(self, dir: Dir) -> Option<Dir> {
+        let from = dir.opposite();
+        if !self.connects(from) {
+            return None;
+        }
+        Dir::try_from(self.0 ^ from.bit()).ok()
+    }
 }
 
-fn has_dir(tile: Tile, dir: Dir) -> bool {
-    just_dir(tile, dir) != 0
+impl TryFrom<u8> for Tile {
+    type Error = TileError;
+
+    /// Accepts no directions at all, or exactly two.
+    fn try_from(bits: u8) -> Result<Tile, TileError> {
+        let all = Dir::ALL.iter().fold(0, |bits, dir| bits | dir.bit());
+        if bits & !all == 0 && matches!(bits.count_ones(), 0 | 2) {
+            Ok(Tile(bits))
+        } else {
+            Err(TileError::Bits(bits))
+        }
+    }
 }
 
-fn flip_tile(tile: Tile) -> Tile {
-    to_tile((tile as u8).rotate_left(4))
+impl TryFrom<char> for Tile {
+    type Error = TileError;
+
+    fn try_from(c: char) -> Result<Tile, TileError> {
+        match c {
+            '-' => Ok(Tile::LEFT_RIGHT),
+            '|' => Ok(Tile::UP_DOWN),
+            'L' => Ok(Tile::UP_RIGHT),
+            'J' => Ok(Tile::UP_LEFT),
+            '7' => Ok(Tile::DOWN_LEFT),
+            'F' => Ok(Tile::DOWN_RIGHT),
+            '.' => Ok(Tile::EMPTY),
+            _ => Err(TileError::Char(c)),
+        }
+    }
 }
 
-fn flip_dir(dir: Dir) -> Dir {
-    to_dir((dir as u8).rotate_left(4))
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TileError {
+    Bits(u8),
+    Char(char),
 }
 
-fn to_dir(val: u8) -> Dir {
-    unsafe {
-        assert!(
-            val == Dir::Right as u8
-                || val == Dir::Down as u8
-                || val == Dir::Left as u8
-                || val == Dir::Up as u8
-        );
-        std::mem::transmute(val)
+impl Display for TileError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TileError::Bits(bits) => write!(f, "invalid tile bits {:#010b}", bits),
+            TileError::Char(c) => write!(f, "unknown tile '{}'", c.escape_debug()),
+        }
     }
 }
 
-fn to_tile(val: u8) -> Tile {
-    unsafe {
-        assert!(
-            val == Tile::LeftRight as u8
-                || val == Tile::UpDown as u8
-                || val == Tile::UpRight as u8
-                || val == Tile::UpLeft as u8
-                || val == Tile::DownLeft as u8
-                || val == Tile::DownRight as u8
-                || val == Tile::Empty as u8
-        );
-        std::mem::transmute(val)
+impl Error for TileError {}
+
+fn parse_tile(c: char) -> Option<Option<Tile>> {
+    match c {
+        'S' => Some(None),
+        c => Tile::try_from(c).ok().map(Some),
     }
 }
 
+/// A field of pipes, and the tile the animal starts on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Maze {
+    pub start: Pos,
+    pub tiles: Grid<Tile>,
+}
+
+/// Reads the maze and works out the pipe under the start tile.
 #[aoc_generator(day10)]
-fn input_generator(input: &str) -> (Pos, Size, HashMap<Pos, Tile>) {
-    let mut start = (0, 0);
-    let mut tiles = HashMap::new();
-    let mut size = (0, 0);
-    for (y, line) in input.lines().enumerate() {
-        size.1 = y + 1;
-        for (x, c) in line.chars().enumerate() {
-            size.0 = x + 1;
-            match parse_tile(c) {
-                None => {
-                    start = (x + 1, y + 1);
-                }
-                Some(tile) => {
-                    tiles.insert((x + 1, y + 1), tile);
+pub fn parse(input: &str) -> Result<Maze, ParseError> {
+    let grid = Grid::parse(DAY, input, "tile", parse_tile)?;
+    let Some(start) = grid.position(Option::is_none) else {
+        return Err(ParseError::at_line(
+            DAY,
+            grid.height(),
+            "no start tile 'S' found",
+        ));
+    };
+    if let Some(((x, y), _)) = grid.iter().filter(|(_, tile)| tile.is_none()).nth(1) {
+        let line = lines(DAY, input).nth(y).unwrap();
+        return Err(line.error_at_column(x, "more than one start tile 'S'"));
+    }
+    let mut tiles = grid.map(|tile| tile.unwrap_or(Tile::EMPTY));
+    let connected = Dir::ALL
+        .into_iter()
+        .filter(|&dir| {
+            tiles
+                .offset(start, dir.delta())
+                .is_some_and(|pos| tiles[pos].connects(dir.opposite()))
+        })
+        .collect::<Vec<_>>();
+    // With more than two pipes leading to the start, keep the longest loop through it.
+    let mut best = None;
+    for (i, &a) in connected.iter().enumerate() {
+        for &b in &connected[i + 1..] {
+            tiles[start] = Tile(a.bit() | b.bit());
+            if let Some(cycle) = trace_loop(start, &tiles) {
+                if best.is_none_or(|(most, _)| cycle.steps > most) {
+                    best = Some((cycle.steps, tiles[start]));
                 }
             }
         }
     }
-    for y in 1..=size.1 {
-        tiles.insert((0, y), Tile::Empty);
-        tiles.insert((size.0 + 1, y), Tile::Empty);
-    }
-    for x in 0..=size.0 + 1 {
-        tiles.insert((x, 0), Tile::Empty);
-        tiles.insert((x, size.1 + 1), Tile::Empty);
-    }
-    let up = just_dir(tiles[&(start.0, start.1 - 1)], Dir::Down);
-    let down = just_dir(tiles[&(start.0, start.1 + 1)], Dir::Up);
-    let left = just_dir(tiles[&(start.0 - 1, start.1)], Dir::Right);
-    let right = just_dir(tiles[&(start.0 + 1, start.1)], Dir::Left);
-    let start_tile = flip_tile(to_tile(up | down | left | right));
-    tiles.insert(start, start_tile);
-    (start, (size.0 + 2, size.1 + 2), tiles)
-}
-
-fn step(pos: Pos, dir: Dir) -> Pos {
-    match dir {
-        Dir::Up => (pos.0, pos.1 - 1),
-        Dir::Down => (pos.0, pos.1 + 1),
-        Dir::Left => (pos.0 - 1, pos.1),
-        Dir::Right => (pos.0 + 1, pos.1),
-    }
+    let Some((_, tile)) = best else {
+        let line = lines(DAY, input).nth(start.1).unwrap();
+        return Err(line.error_at_column(start.0, "cannot determine start tile"));
+    };
+    tiles[start] = tile;
+    Ok(Maze { start, tiles })
 }
 
-fn turn(tile: Tile, dir: Dir) -> Dir {
-    to_dir((tile as u8) ^ (flip_dir(dir) as u8))
+/// The loop through the start tile.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cycle {
+    /// Which tiles are on the loop.
+    pub visited: Grid<bool>,
+    /// The tiles where the loop turns, in the order it passes them.
+    pub corners: Vec<Pos>,
+    pub steps: usize,
 }
 
-fn walk_cycle(start: &Pos, tiles: &HashMap<Pos, Tile>) -> (HashSet<Pos>, usize) {
+/// Follows the pipes from `start` back to it, or `None` if they lead anywhere else.
+fn trace_loop(start: Pos, tiles: &Grid<Tile>) -> Option<Cycle> {
     let mut loc = Loc {
-        pos: *start,
-        dir: *[Dir::Up, Dir::Down, Dir::Left, Dir::Right]
-            .iter()
-            .find(|&dir| has_dir(tiles[&start], *dir))
-            .unwrap(),
+        pos: start,
+        dir: tiles[start].dirs().next()?,
     };
-    let mut visited = HashSet::new();
-    let steps = (1..)
-        .find(|_| {
-            visited.insert(loc.pos);
-            loc.pos = step(loc.pos, loc.dir);
-            loc.dir = turn(tiles[&loc.pos], loc.dir);
-            visited.contains(&loc.pos)
-        })
-        .unwrap();
-    (visited, steps)
+    let mut visited = Grid::filled(tiles.width(), tiles.height(), false);
+    let mut corners = vec![];
+    for steps in 1.. {
+        visited[loc.pos] = true;
+        loc.pos = tiles.offset(loc.pos, loc.dir.delta())?;
+        let dir = tiles[loc.pos].turn(loc.dir)?;
+        if dir != loc.dir {
+            corners.push(loc.pos);
+        }
+        loc.dir = dir;
+        if loc.pos == start {
+            return Some(Cycle {
+                visited,
+                corners,
+                steps,
+            });
+        }
+        if visited[loc.pos] {
+            return None;
+        }
+    }
+    unreachable!()
 }
 
+pub fn walk_cycle(start: &Pos, tiles: &Grid<Tile>) -> Cycle {
+    trace_loop(*start, tiles).expect("start is on a loop")
+}
+
+/// The number of steps to the point of the loop farthest from the start.
 #[aoc(day10, part1)]
-fn part1(input: &(Pos, Size, HashMap<Pos, Tile>)) -> usize {
-    let (start, _, tiles) = input;
-    let (_, steps) = walk_cycle(start, tiles);
-    steps / 2
+pub fn part1(maze: &Maze) -> usize {
+    let Maze { start, tiles, .. } = maze;
+    walk_cycle(start, tiles).steps / 2
 }
 
 #[derive(PartialEq)]
@@ -174,39 +283,259 @@ enum State {
     Outside,
 }
 
-#[aoc(day10, part2)]
-fn part2(input: &(Pos, Size, HashMap<Pos, Tile>)) -> usize {
-    let (start, size, tiles) = input;
-    let (visited, _) = walk_cycle(start, tiles);
-    let mut count = 0;
-    for y in 1..size.1 - 1 {
+/// Where a tile lies relative to the loop.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Region {
+    Loop,
+    Inside,
+    Outside,
+}
+
+/// Sorts every tile into the loop, inside it or outside it, by scanning each row and tracking
+/// whether the loop has been crossed an odd number of times.
+pub fn regions(maze: &Maze) -> Grid<Region> {
+    let Maze { start, tiles } = maze;
+    let Cycle { visited, .. } = walk_cycle(start, tiles);
+    let mut regions = Grid::filled(tiles.width(), tiles.height(), Region::Outside);
+    for y in 0..tiles.height() {
         let mut state = State::Outside;
-        for x in 1..size.0 - 1 {
-            if !visited.contains(&(x, y)) {
+        for x in 0..tiles.width() {
+            if !visited[(x, y)] {
                 if state == State::Inside {
-                    count += 1;
+                    regions[(x, y)] = Region::Inside;
                 }
                 continue;
             }
-            state = match (&state, tiles[&(x, y)]) {
-                (State::Outside, Tile::UpDown) => State::Inside,
-                (State::Outside, Tile::DownRight) => State::UpperEdge,
-                (State::Outside, Tile::UpRight) => State::LowerEdge,
+            regions[(x, y)] = Region::Loop;
+            state = match (&state, tiles[(x, y)]) {
+                (State::Outside, Tile::UP_DOWN) => State::Inside,
+                (State::Outside, Tile::DOWN_RIGHT) => State::UpperEdge,
+                (State::Outside, Tile::UP_RIGHT) => State::LowerEdge,
                 (State::Outside, _) => state,
-                (State::Inside, Tile::UpDown) => State::Outside,
-                (State::Inside, Tile::UpRight) => State::UpperEdge,
-                (State::Inside, Tile::DownRight) => State::LowerEdge,
+                (State::Inside, Tile::UP_DOWN) => State::Outside,
+                (State::Inside, Tile::UP_RIGHT) => State::UpperEdge,
+                (State::Inside, Tile::DOWN_RIGHT) => State::LowerEdge,
                 (State::Inside, _) => State::Outside,
-                (State::UpperEdge, Tile::UpLeft) => State::Inside,
-                (State::UpperEdge, Tile::DownLeft) => State::Outside,
-                (State::LowerEdge, Tile::DownLeft) => State::Inside,
-                (State::LowerEdge, Tile::UpLeft) => State::Outside,
-                (_, Tile::LeftRight) => state,
+                (State::UpperEdge, Tile::UP_LEFT) => State::Inside,
+                (State::UpperEdge, Tile::DOWN_LEFT) => State::Outside,
+                (State::LowerEdge, Tile::DOWN_LEFT) => State::Inside,
+                (State::LowerEdge, Tile::UP_LEFT) => State::Outside,
+                (_, Tile::LEFT_RIGHT) => state,
                 _ => panic!(),
             }
         }
     }
-    count
+    regions
+}
+
+/// Counts the tiles enclosed by the loop, by scanning each row for crossings.
+#[aoc(day10, part2)]
+pub fn part2(maze: &Maze) -> usize {
+    regions(maze)
+        .iter()
+        .filter(|(_, &region)| region == Region::Inside)
+        .count()
+}
+
+/// Counts the enclosed tiles from the loop's area, by the shoelace formula over its corners, and
+/// Pick's theorem: the area is the number of tiles inside plus half those on the loop, minus one.
+#[aoc(day10, part2, shoelace)]
+pub fn part2_shoelace(maze: &Maze) -> usize {
+    let Cycle { corners, steps, .. } = walk_cycle(&maze.start, &maze.tiles);
+    let twice_area = corners
+        .iter()
+        .zip(corners.iter().cycle().skip(1))
+        .map(|(&(x1, y1), &(x2, y2))| (x1 * y2) as i64 - (x2 * y1) as i64)
+        .sum::<i64>()
+        .unsigned_abs() as usize;
+    (twice_area + 2 - steps) / 2
+}
+
+/// Tiles off the loop that can reach each other, squeezing between pipes if they need to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Area {
+    pub tiles: Vec<Pos>,
+    /// Whether the area reaches the edge of the maze.
+    pub outside: bool,
+}
+
+/// The tiles off the loop, split into areas by flood filling a grid of twice the resolution,
+/// where the gaps between adjacent pipes that do not connect are cells of their own.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FloodFill {
+    /// The area each tile belongs to, or `None` for tiles on the loop.
+    area_of: Grid<Option<usize>>,
+    pub areas: Vec<Area>,
+}
+
+impl FloodFill {
+    pub fn new(maze: &Maze) -> FloodFill {
+        let Maze { start, tiles } = maze;
+        let Cycle { visited, .. } = walk_cycle(start, tiles);
+        // Tile (x, y) is cell (2x + 1, 2y + 1), leaving a ring of cells around the maze that
+        // connects everything outside it.
+        let mut walls = Grid::filled(2 * tiles.width() + 1, 2 * tiles.height() + 1, false);
+        for ((x, y), _) in visited.iter().filter(|(_, &on_loop)| on_loop) {
+            let centre = (2 * x + 1, 2 * y + 1);
+            walls[centre] = true;
+            for dir in tiles[(x, y)].dirs() {
+                let gap = walls.offset(centre, dir.delta()).unwrap();
+                walls[gap] = true;
+            }
+        }
+        let mut cell_area = walls.map(|_| None);
+        let mut areas = vec![];
+        for cell in walls.positions() {
+            if walls[cell] || cell_area[cell].is_some() {
+                continue;
+            }
+            let id = areas.len();
+            let mut area = Area {
+                tiles: vec![],
+                outside: cell == (0, 0),
+            };
+            cell_area[cell] = Some(id);
+            let mut queue = VecDeque::from([cell]);
+            while let Some(cell @ (x, y)) = queue.pop_front() {
+                if x % 2 == 1 && y % 2 == 1 {
+                    area.tiles.push((x / 2, y / 2));
+                }
+                for next in walls.neighbours4(cell) {
+                    if !walls[next] && cell_area[next].is_none() {
+                        cell_area[next] = Some(id);
+                        queue.push_back(next);
+                    }
+                }
+            }
+            area.tiles.sort_by_key(|&(x, y)| (y, x));
+            areas.push(area);
+        }
+        let area_of = Grid::new(
+            tiles.width(),
+            tiles.height(),
+            tiles
+                .positions()
+                .map(|(x, y)| cell_area[(2 * x + 1, 2 * y + 1)])
+                .collect(),
+        );
+        FloodFill { area_of, areas }
+    }
+
+    pub fn area_of(&self, pos: Pos) -> Option<&Area> {
+        self.area_of[pos].map(|id| &self.areas[id])
+    }
+
+    /// Whether the tile is off the loop and can get out of the maze from where it is.
+    pub fn reachable_from_border(&self, pos: Pos) -> bool {
+        self.area_of(pos).is_some_and(|area| area.outside)
+    }
+
+    pub fn inside(&self) -> usize {
+        self.areas
+            .iter()
+            .filter(|area| !area.outside)
+            .map(|area| area.tiles.len())
+            .sum()
+    }
+}
+
+/// Counts the enclosed tiles by flood filling from outside the maze.
+#[aoc(day10, part2, squeeze)]
+pub fn part2_squeeze(maze: &Maze) -> usize {
+    FloodFill::new(maze).inside()
+}
+
+/// Draws the loop with box-drawing characters, and marks the other tiles `I` or `O` for inside
+/// or outside it. Tiles holding pipes that are not on the loop are dimmed with ANSI escapes if
+/// `ansi` is set, or written in lower case otherwise.
+pub fn render(maze: &Maze, ansi: bool) -> String {
+    let regions = regions(maze);
+    let mut out = String::new();
+    for (pos, &region) in regions.iter() {
+        let tile = maze.tiles[pos];
+        let mark = match region {
+            Region::Loop => tile.box_char(),
+            Region::Inside => 'I',
+            Region::Outside => 'O',
+        };
+        match (region, tile == Tile::EMPTY, ansi) {
+            (Region::Loop, _, _) | (_, true, _) => out.push(mark),
+            (_, false, true) => write!(out, "\x1b[2m{}\x1b[0m", mark).unwrap(),
+            (_, false, false) => out.push(mark.to_ascii_lowercase()),
+        }
+        if pos.0 == regions.width() - 1 {
+            out.push('\n');
+        }
+    }
+    out
+}
+
+fn colour(region: Region, junk: bool) -> [u8; 3] {
+    match (region, junk) {
+        (Region::Loop, _) => [40, 70, 170],
+        (Region::Inside, false) => [250, 200, 60],
+        (Region::Inside, true) => [190, 150, 40],
+        (Region::Outside, false) => [235, 235, 235],
+        (Region::Outside, true) => [180, 180, 180],
+    }
+}
+
+/// The same classification as `render`, as a binary PPM image with `scale` pixels per tile.
+pub fn to_ppm(maze: &Maze, scale: usize) -> Vec<u8> {
+    let regions = regions(maze);
+    let (width, height) = (regions.width() * scale, regions.height() * scale);
+    let mut image = format!("P6\n{} {}\n255\n", width, height).into_bytes();
+    for y in 0..height {
+        for x in 0..width {
+            let pos = (x / scale, y / scale);
+            image.extend(colour(regions[pos], maze.tiles[pos] != Tile::EMPTY));
+        }
+    }
+    image
+}
+
+/// The same classification as `render`, as an SVG image with the loop drawn through the
+/// centres of its tiles.
+pub fn to_svg(maze: &Maze) -> String {
+    const SIZE: usize = 10;
+    let regions = regions(maze);
+    let hex = |[r, g, b]: [u8; 3]| format!("#{:02x}{:02x}{:02x}", r, g, b);
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{1}\" viewBox=\"0 0 {0} {1}\">\n",
+        regions.width() * SIZE,
+        regions.height() * SIZE
+    );
+    for (pos, &region) in regions.iter() {
+        if region == Region::Loop {
+            continue;
+        }
+        let fill = hex(colour(region, maze.tiles[pos] != Tile::EMPTY));
+        writeln!(
+            svg,
+            "  <rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\"/>",
+            pos.0 * SIZE,
+            pos.1 * SIZE,
+            SIZE,
+            SIZE,
+            fill
+        )
+        .unwrap();
+    }
+    let points = walk_cycle(&maze.start, &maze.tiles)
+        .corners
+        .iter()
+        .map(|&(x, y)| format!("{},{}", x * SIZE + SIZE / 2, y * SIZE + SIZE / 2))
+        .collect::<Vec<_>>();
+    writeln!(
+        svg,
+        "  <polygon points=\"{}\" fill=\"none\" stroke=\"{}\" stroke-width=\"{}\"/>",
+        points.join(" "),
+        hex(colour(Region::Loop, false)),
+        SIZE / 3
+    )
+    .unwrap();
+    svg.push_str("</svg>\n");
+    svg
 }
 
 #[cfg(test)]
@@ -220,7 +549,7 @@ mod test {
 L|7||
 -L-J|
 L|-JF";
-        assert_eq!(part1(&input_generator(input)), 4)
+        assert_eq!(part1(&parse(input).unwrap()), 4)
     }
 
     #[test]
@@ -230,7 +559,7 @@ L|-JF";
 SJLL7
 |F--J
 LJ.LJ";
-        assert_eq!(part1(&input_generator(input)), 8)
+        assert_eq!(part1(&parse(input).unwrap()), 8)
     }
 
     #[test]
@@ -244,7 +573,10 @@ LJ.LJ";
 .|..|.|..|.
 .L--J.L--J.
 ...........";
-        assert_eq!(part2(&input_generator(input)), 4)
+        let maze = parse(input).unwrap();
+        assert_eq!(part2(&maze), 4);
+        assert_eq!(part2_shoelace(&maze), 4);
+        assert_eq!(part2_squeeze(&maze), 4);
     }
 
     #[test]
@@ -258,7 +590,10 @@ LJ.LJ";
 .|..||..|.
 .L--JL--J.
 ..........";
-        assert_eq!(part2(&input_generator(input)), 4)
+        let maze = parse(input).unwrap();
+        assert_eq!(part2(&maze), 4);
+        assert_eq!(part2_shoelace(&maze), 4);
+        assert_eq!(part2_squeeze(&maze), 4);
     }
 
     #[test]
@@ -273,7 +608,10 @@ L--J.L7...LJS7F-7L7.
 .....|FJLJ|FJ|F7|.LJ
 ....FJL-7.||.||||...
 ....L---J.LJ.LJLJ...";
-        assert_eq!(part2(&input_generator(input)), 8)
+        let maze = parse(input).unwrap();
+        assert_eq!(part2(&maze), 8);
+        assert_eq!(part2_shoelace(&maze), 8);
+        assert_eq!(part2_squeeze(&maze), 8);
     }
 
     #[test]
@@ -288,6 +626,142 @@ L---JF-JLJ.||-FJLJJ7
 7-L-JL7||F7|L7F-7F7|
 L.L7LFJ|||||FJL7||LJ
 L7JLJL-JLJLJL--JLJ.L";
-        assert_eq!(part2(&input_generator(input)), 10)
+        let maze = parse(input).unwrap();
+        assert_eq!(part2(&maze), 10);
+        assert_eq!(part2_shoelace(&maze), 10);
+        assert_eq!(part2_squeeze(&maze), 10);
+    }
+
+    #[test]
+    fn nothing_enclosed() {
+        for input in ["S7\nLJ", "S--7\nL--J"] {
+            let maze = parse(input).unwrap();
+            assert_eq!(part2(&maze), 0, "{}", input);
+            assert_eq!(part2_shoelace(&maze), 0, "{}", input);
+            assert_eq!(part2_squeeze(&maze), 0, "{}", input);
+        }
+    }
+
+    #[test]
+    fn squeezing() {
+        let input = "..........
+.S------7.
+.|F----7|.
+.||....||.
+.||....||.
+.|L-7F-J|.
+.|..||..|.
+.L--JL--J.
+..........";
+        let fill = FloodFill::new(&parse(input).unwrap());
+        let mut sizes = fill
+            .areas
+            .iter()
+            .map(|area| (area.outside, area.tiles.len()))
+            .collect::<Vec<_>>();
+        sizes.sort();
+        assert_eq!(sizes, vec![(false, 4), (true, 42)]);
+        // The two pockets meet through the gap between the loop's parallel sides.
+        assert_eq!(
+            fill.area_of((2, 6)).unwrap().tiles,
+            vec![(2, 6), (3, 6), (6, 6), (7, 6)]
+        );
+        assert!(fill.reachable_from_border((4, 3)));
+        assert!(!fill.reachable_from_border((7, 6)));
+        assert!(!fill.reachable_from_border((1, 1)));
+        assert_eq!(fill.area_of((1, 1)), None);
+    }
+
+    #[test]
+    fn rendering() {
+        let input = "...........
+.S-------7.
+.|F-----7|.
+.||.....||.
+.||.....||.
+.|L-7.F-J|.
+.|..|.|..|.
+.L--J.L--J.
+...........";
+        let expected = "OOOOOOOOOOO
+O┌───────┐O
+O│┌─────┐│O
+O││OOOOO││O
+O││OOOOO││O
+O│└─┐O┌─┘│O
+O│II│O│II│O
+O└──┘O└──┘O
+OOOOOOOOOOO
+";
+        let maze = parse(input).unwrap();
+        assert_eq!(render(&maze, false), expected);
+        let svg = to_svg(&maze);
+        assert_eq!(svg.matches("<rect").count(), 99 - 46);
+        assert!(svg.contains("<polygon points=\"15,75 45,75 45,55 25,55 25,25 85,25 "));
+
+        let junk = parse("-L|F7\n7S-7|\nL|7||\n-L-J|\nL|-JF").unwrap();
+        assert_eq!(render(&junk, false), "ooooo\no┌─┐o\no│i│o\no└─┘o\nooooo\n");
+        assert!(render(&junk, true).starts_with("\x1b[2mO\x1b[0m"));
+        let ppm = to_ppm(&junk, 2);
+        assert!(ppm.starts_with(b"P6\n10 10\n255\n"));
+        assert_eq!(ppm.len(), "P6\n10 10\n255\n".len() + 10 * 10 * 3);
+    }
+
+    #[test]
+    fn start_on_edge() {
+        let input = "S-7\n|.|\nL-J";
+        let maze = parse(input).unwrap();
+        assert_eq!(maze.tiles[(0, 0)], Tile::DOWN_RIGHT);
+        assert_eq!(part1(&maze), 4);
+    }
+
+    #[test]
+    fn ambiguous_start() {
+        // Three pipes lead to S, but the one from the left never comes back.
+        let input = ".....\n.F-7.\n-S.|.\n.L-J.";
+        let maze = parse(input).unwrap();
+        assert_eq!(maze.tiles[(1, 2)], Tile::UP_DOWN);
+        assert_eq!(part1(&maze), 4);
+    }
+
+    #[test]
+    fn conversions() {
+        assert_eq!(Tile::try_from('7'), Ok(Tile::DOWN_LEFT));
+        assert_eq!(Tile::try_from('S'), Err(TileError::Char('S')));
+        assert_eq!(Tile::try_from(Tile::UP_RIGHT.bits()), Ok(Tile::UP_RIGHT));
+        assert_eq!(Tile::try_from(0), Ok(Tile::EMPTY));
+        assert_eq!(Tile::try_from(Dir::Up.bit()), Err(TileError::Bits(64)));
+        assert_eq!(Tile::try_from(2), Err(TileError::Bits(2)));
+        assert_eq!(Dir::try_from(16), Ok(Dir::Left));
+        assert_eq!(
+            TileError::Bits(3).to_string(),
+            "invalid tile bits 0b00000011"
+        );
+        assert_eq!(Tile::UP_LEFT.turn(Dir::Right), Some(Dir::Up));
+        assert_eq!(Tile::UP_LEFT.turn(Dir::Left), None);
+        for dir in Dir::ALL {
+            assert_eq!(Dir::try_from(dir.bit().rotate_left(4)), Ok(dir.opposite()));
+        }
+    }
+
+    #[test]
+    fn bad_input() {
+        let err = parse(".....\n.S-7.\n.|.|.\n.L-J?").unwrap_err();
+        assert_eq!(err.to_string(), "day10 line 4 col 5: unknown tile '?'");
+        let err = parse(".....\n.F-7.\n.|.|.\n.L-J.").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "day10 line 4 col 1: no start tile 'S' found"
+        );
+        let err = parse(".....\n.S-7.\n...|.\n.L-J.").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "day10 line 2 col 2: cannot determine start tile"
+        );
+        let err = parse(".....\n.S-7.\n.|.|.\n.L-S.").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "day10 line 4 col 4: more than one start tile 'S'"
+        );
     }
 }
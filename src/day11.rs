@@ -1,57 +1,182 @@
 use aoc_runner_derive::aoc;
 use aoc_runner_derive::aoc_generator;
 
-use std::collections::HashSet;
+use std::collections::HashMap;
 
-type Pos = (usize, usize);
+use crate::grid::Grid;
+use crate::parse::{lines, ParseError};
 
+const DAY: u32 = 11;
+
+pub use crate::grid::Pos;
+
+/// The galaxies in the image, and the rows and columns containing none of them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Image {
+    pub galaxies: Vec<Pos>,
+    pub empty_rows: Vec<usize>,
+    pub empty_columns: Vec<usize>,
+    /// How many galaxies each row holds.
+    pub row_counts: Vec<usize>,
+    /// How many galaxies each column holds.
+    pub column_counts: Vec<usize>,
+}
+
+/// Reads the image of galaxies and empty space.
 #[aoc_generator(day11)]
-fn input_generator(input: &str) -> (Vec<Pos>, Vec<usize>, Vec<usize>) {
-    let input_width = input.lines().next().unwrap().len();
-    let mut empty_columns = (0..input_width).collect::<HashSet<usize>>();
-    let mut empty_rows = vec![];
-    let galaxies = input
-        .lines()
-        .enumerate()
-        .flat_map(|(y, line)| {
-            let row_galaxies = line
-                .chars()
-                .enumerate()
-                .filter_map(|(x, c)| match c {
-                    '#' => {
-                        empty_columns.remove(&x);
-                        Some((x, y))
-                    }
-                    _ => None,
-                })
-                .collect::<Vec<_>>();
-            if row_galaxies.is_empty() {
-                empty_rows.push(y);
-            }
-            row_galaxies
-        })
+pub fn parse(input: &str) -> Result<Image, ParseError> {
+    let grid = Grid::parse(DAY, input, "pixel", |c| match c {
+        '#' => Some(true),
+        '.' => Some(false),
+        _ => None,
+    })?;
+    let galaxies = grid
+        .iter()
+        .filter(|(_, &galaxy)| galaxy)
+        .map(|(pos, _)| pos)
         .collect();
-    let mut empty_columns = Vec::from_iter(empty_columns);
-    empty_columns.sort();
-    (galaxies, empty_rows, empty_columns)
+    let row_counts = grid
+        .rows()
+        .map(|row| row.iter().filter(|&&galaxy| galaxy).count())
+        .collect::<Vec<_>>();
+    let column_counts = grid
+        .columns()
+        .map(|column| column.filter(|&&galaxy| galaxy).count())
+        .collect::<Vec<_>>();
+    let empty = |counts: &[usize]| (0..counts.len()).filter(|&i| counts[i] == 0).collect();
+    Ok(Image {
+        galaxies,
+        empty_rows: empty(&row_counts),
+        empty_columns: empty(&column_counts),
+        row_counts,
+        column_counts,
+    })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Axis {
+    Row,
+    Column,
+}
+
+/// How far space expands: how wide a line of the image becomes, given which line it is and how
+/// many galaxies it holds.
+pub trait Expansion {
+    fn width(&self, axis: Axis, line: usize, galaxies: usize) -> usize;
+}
+
+impl<F: Fn(Axis, usize, usize) -> usize> Expansion for F {
+    fn width(&self, axis: Axis, line: usize, galaxies: usize) -> usize {
+        self(axis, line, galaxies)
+    }
+}
+
+/// Widths for the empty lines, one for rows and one for columns, unless given for the line
+/// itself. Lines with galaxies stay as they are.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineFactors {
+    pub rows: usize,
+    pub columns: usize,
+    pub lines: HashMap<(Axis, usize), usize>,
+}
+
+impl LineFactors {
+    pub fn uniform(factor: usize) -> LineFactors {
+        LineFactors {
+            rows: factor,
+            columns: factor,
+            lines: HashMap::new(),
+        }
+    }
+}
+
+impl Expansion for LineFactors {
+    fn width(&self, axis: Axis, line: usize, galaxies: usize) -> usize {
+        if galaxies > 0 {
+            return 1;
+        }
+        self.lines
+            .get(&(axis, line))
+            .copied()
+            .unwrap_or(match axis {
+                Axis::Row => self.rows,
+                Axis::Column => self.columns,
+            })
+    }
+}
+
+/// Reads line factors from lines of `rows <factor>`, `columns <factor>`, `row <y> <factor>` and
+/// `column <x> <factor>`. Rows and columns not mentioned do not expand.
+pub fn parse_factors(config: &str) -> Result<LineFactors, ParseError> {
+    let mut factors = LineFactors::uniform(1);
+    for line in lines(DAY, config) {
+        let mut words = line.text.split_whitespace();
+        let Some(key) = words.next() else {
+            continue;
+        };
+        let mut number = |what| line.parse::<usize>(line.next(&mut words, what)?);
+        match key {
+            "rows" => factors.rows = number("factor")?,
+            "columns" => factors.columns = number("factor")?,
+            "row" | "column" => {
+                let axis = if key == "row" {
+                    Axis::Row
+                } else {
+                    Axis::Column
+                };
+                let index = number(key)?;
+                factors.lines.insert((axis, index), number("factor")?);
+            }
+            _ => return Err(line.error_at(key, format!("unknown setting '{}'", key))),
+        }
+        if let Some(extra) = words.next() {
+            return Err(line.error_at(extra, "unexpected text after setting"));
+        }
+    }
+    Ok(factors)
 }
 
-fn real_coords(factor: usize, galaxy: &Pos, empty_rows: &[usize], empty_columns: &[usize]) -> Pos {
-    (
-        galaxy.0 + (factor - 1) * empty_columns.iter().filter(|&cx| *cx < galaxy.0).count(),
-        galaxy.1 + (factor - 1) * empty_rows.iter().filter(|&ry| *ry < galaxy.1).count(),
-    )
+/// Expands lines the sparser they are: a line with `n` galaxies becomes `factor / (n + 1)` wide,
+/// but never narrower than it was. Empty lines grow by the whole factor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Density {
+    pub factor: usize,
 }
 
-fn expand_space(factor: usize, input: &(Vec<Pos>, Vec<usize>, Vec<usize>)) -> Vec<Pos> {
-    let (galaxies, empty_rows, empty_columns) = input;
-    galaxies
+impl Expansion for Density {
+    fn width(&self, _: Axis, _: usize, galaxies: usize) -> usize {
+        (self.factor / (galaxies + 1)).max(1)
+    }
+}
+
+/// Where each line starts once the lines before it have expanded.
+fn offsets<E: Expansion + ?Sized>(expansion: &E, axis: Axis, counts: &[usize]) -> Vec<usize> {
+    counts
+        .iter()
+        .enumerate()
+        .scan(0, |offset, (line, &galaxies)| {
+            let start = *offset;
+            *offset += expansion.width(axis, line, galaxies);
+            Some(start)
+        })
+        .collect()
+}
+
+pub fn expand_with<E: Expansion + ?Sized>(expansion: &E, image: &Image) -> Vec<Pos> {
+    let xs = offsets(expansion, Axis::Column, &image.column_counts);
+    let ys = offsets(expansion, Axis::Row, &image.row_counts);
+    image
+        .galaxies
         .iter()
-        .map(|g| real_coords(factor, g, empty_rows, empty_columns))
+        .map(|&(x, y)| (xs[x], ys[y]))
         .collect()
 }
 
-fn sum_distances(galaxies: &[Pos]) -> usize {
+pub fn expand_space(factor: usize, image: &Image) -> Vec<Pos> {
+    expand_with(&LineFactors::uniform(factor), image)
+}
+
+pub fn sum_distances(galaxies: &[Pos]) -> usize {
     let mut distance = 0;
     for (i, g1) in galaxies.iter().enumerate() {
         for g2 in galaxies.iter().skip(i + 1) {
@@ -61,14 +186,16 @@ fn sum_distances(galaxies: &[Pos]) -> usize {
     distance
 }
 
+/// Sums the distances between galaxies, with each empty row and column doubled.
 #[aoc(day11, part1)]
-fn part1(input: &(Vec<Pos>, Vec<usize>, Vec<usize>)) -> usize {
-    sum_distances(&expand_space(2, input))
+pub fn part1(image: &Image) -> usize {
+    sum_distances(&expand_space(2, image))
 }
 
+/// Sums the distances between galaxies, with each empty row and column a million wide.
 #[aoc(day11, part2)]
-fn part2(input: &(Vec<Pos>, Vec<usize>, Vec<usize>)) -> usize {
-    sum_distances(&expand_space(1_000_000, input))
+pub fn part2(image: &Image) -> usize {
+    sum_distances(&expand_space(1_000_000, image))
 }
 
 #[cfg(test)]
@@ -88,18 +215,86 @@ mod test {
 
     #[test]
     fn sample1() {
-        assert_eq!(part1(&input_generator(INPUT)), 374)
+        assert_eq!(part1(&parse(INPUT).unwrap()), 374)
     }
 
     #[test]
     fn sample2() {
         assert_eq!(
-            sum_distances(&expand_space(10, &input_generator(INPUT))),
+            sum_distances(&expand_space(10, &parse(INPUT).unwrap())),
             1030
         );
         assert_eq!(
-            sum_distances(&expand_space(100, &input_generator(INPUT))),
+            sum_distances(&expand_space(100, &parse(INPUT).unwrap())),
             8410
         )
     }
+
+    #[test]
+    fn expansion_models() {
+        let image = parse(INPUT).unwrap();
+        // Each empty line widened separately by rows and columns, the way it used to be done.
+        let expected = |rows: usize, columns: usize| {
+            let galaxies = image
+                .galaxies
+                .iter()
+                .map(|&(x, y)| {
+                    let before = |empty: &[usize], i| empty.iter().filter(|&&e| e < i).count();
+                    (
+                        x + (columns - 1) * before(&image.empty_columns, x),
+                        y + (rows - 1) * before(&image.empty_rows, y),
+                    )
+                })
+                .collect::<Vec<_>>();
+            sum_distances(&galaxies)
+        };
+        let factors = LineFactors {
+            rows: 10,
+            columns: 100,
+            lines: HashMap::new(),
+        };
+        assert_eq!(
+            sum_distances(&expand_with(&factors, &image)),
+            expected(10, 100)
+        );
+        let by_axis = |axis, _, galaxies| match (axis, galaxies) {
+            (Axis::Row, 0) => 7,
+            (Axis::Column, 0) => 3,
+            _ => 1,
+        };
+        assert_eq!(
+            sum_distances(&expand_with(&by_axis, &image)),
+            expected(7, 3)
+        );
+
+        let small = parse("#.#\n...\n#..").unwrap();
+        let factors = parse_factors("columns 3\nrow 1 5\n").unwrap();
+        assert_eq!(expand_with(&factors, &small), vec![(0, 0), (4, 0), (0, 6)]);
+        assert_eq!(
+            expand_with(&Density { factor: 6 }, &small),
+            vec![(0, 0), (8, 0), (0, 8)]
+        );
+    }
+
+    #[test]
+    fn bad_factors() {
+        let err = parse_factors("rows 2\nlines 3").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "day11 line 2 col 1: unknown setting 'lines'"
+        );
+        let err = parse_factors("row 2").unwrap_err();
+        assert_eq!(err.to_string(), "day11 line 1 col 6: missing factor");
+        let err = parse_factors("columns 2 3").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "day11 line 1 col 11: unexpected text after setting"
+        );
+    }
+
+    #[test]
+    fn bad_pixel() {
+        let err = parse("...#\n.*..").unwrap_err();
+        assert_eq!(err.to_string(), "day11 line 2 col 2: unknown pixel '*'");
+    }
 }
@@ -1,45 +1,68 @@
 use aoc_runner_derive::aoc;
+use aoc_runner_derive::aoc_generator;
 
 use std::cmp::Ordering;
 use std::collections::HashMap;
 
-type Slot<'a> = &'a str;
-type Row<'a> = Vec<Slot<'a>>;
-type GroupSize = usize;
-
-fn input_generator(input: &str) -> Vec<(Row, Vec<GroupSize>)> {
-    input
-        .lines()
-        .map(|line| {
-            let (row, groups) = line.split_once(' ').unwrap();
-            let groups = groups.split(',').map(|s| s.parse().unwrap()).collect();
-            let row = row.split('.').filter(|r| !r.is_empty()).collect();
-            (row, groups)
-        })
-        .collect()
+use crate::parse::{lines, Line, ParseError};
+
+const DAY: u32 = 12;
+
+/// A run of damaged or unknown springs, between operational ones.
+pub type Slot<'a> = &'a str;
+/// The size of a contiguous group of damaged springs.
+pub type GroupSize = usize;
+
+/// A row of springs, some damaged (`#`) and some unknown (`?`), and the sizes of its groups of
+/// damaged springs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Record {
+    pub row: String,
+    pub groups: Vec<GroupSize>,
 }
 
-fn input_generator_part2(input: &str) -> Vec<(Vec<String>, Vec<GroupSize>)> {
-    input
-        .lines()
-        .map(|line| {
-            let (row, groups) = line.split_once(' ').unwrap();
-            let groups: Vec<GroupSize> = groups.split(',').map(|s| s.parse().unwrap()).collect();
-            let groups = vec![groups].into_iter().cycle().take(5).flatten().collect();
-            let row: String = vec![row]
-                .into_iter()
-                .cycle()
-                .take(5)
-                .intersperse("?")
-                .collect();
-            let row = row
-                .split('.')
-                .filter(|r| !r.is_empty())
-                .map(|s| s.to_string())
-                .collect();
-            (row, groups)
-        })
-        .collect()
+impl Record {
+    pub fn unfold(&self, copies: usize) -> Record {
+        let row = std::iter::repeat_n(self.row.as_str(), copies)
+            .intersperse("?")
+            .collect();
+        let groups = self.groups.repeat(copies);
+        Record { row, groups }
+    }
+
+    pub fn slots(&self) -> Vec<Slot<'_>> {
+        self.row.split('.').filter(|r| !r.is_empty()).collect()
+    }
+
+    /// The number of ways the unknown springs could be filled in to match the groups.
+    pub fn arrangements(&self) -> usize {
+        count_arrangements(&self.slots(), &self.groups, &mut HashMap::new())
+    }
+}
+
+fn parse_record(line: Line<'_>) -> Result<Record, ParseError> {
+    let (row, groups) = line.split_once(line.text, " ")?;
+    if let Some((x, c)) = row
+        .chars()
+        .enumerate()
+        .find(|(_, c)| !matches!(c, '.' | '#' | '?'))
+    {
+        return Err(line.unknown_char(x, c, "spring"));
+    }
+    let groups = groups
+        .split(',')
+        .map(|s| line.parse(s))
+        .collect::<Result<_, _>>()?;
+    Ok(Record {
+        row: row.to_string(),
+        groups,
+    })
+}
+
+/// Reads one condition record per line.
+#[aoc_generator(day12)]
+pub fn parse(input: &str) -> Result<Vec<Record>, ParseError> {
+    lines(DAY, input).map(parse_record).collect()
 }
 
 fn placements<'a, 'b>(
@@ -63,7 +86,8 @@ fn placements<'a, 'b>(
         })
 }
 
-type Memory<'a> = HashMap<(Vec<Slot<'a>>, Vec<GroupSize>), usize>;
+/// Counts of arrangements already worked out, by the slots and groups left to place.
+pub type Memory<'a> = HashMap<(Vec<Slot<'a>>, Vec<GroupSize>), usize>;
 
 fn recall(memory: &Memory, slots: &[Slot], groups: &[GroupSize]) -> Option<usize> {
     memory.get(&(slots.to_vec(), groups.to_vec())).copied()
@@ -73,7 +97,9 @@ fn record<'a>(memory: &mut Memory<'a>, slots: &[Slot<'a>], groups: &[GroupSize],
     memory.insert((slots.to_owned(), groups.to_owned()), result);
 }
 
-fn count_arrangements<'a, 'b>(
+/// The number of ways to place `groups` of damaged springs, in order, into the runs of springs
+/// between operational ones, remembering the counts already worked out in `memory`.
+pub fn count_arrangements<'a, 'b>(
     slots: &'b [Slot<'a>],
     groups: &'b [GroupSize],
     memory: &mut Memory<'a>,
@@ -137,27 +163,18 @@ fn count_arrangements<'a, 'b>(
     result
 }
 
+/// Sums the number of arrangements of each record.
 #[aoc(day12, part1)]
-fn part1(input: &str) -> usize {
-    let input = input_generator(input);
-    input
-        .iter()
-        .map(|(slots, groups)| count_arrangements(slots, groups, &mut HashMap::new()))
-        .sum()
+pub fn part1(records: &[Record]) -> usize {
+    records.iter().map(Record::arrangements).sum()
 }
 
+/// Sums the number of arrangements of each record unfolded five times.
 #[aoc(day12, part2)]
-fn part2(input: &str) -> usize {
-    let input = input_generator_part2(input);
-    input
+pub fn part2(records: &[Record]) -> usize {
+    records
         .iter()
-        .map(|(slots, groups)| {
-            let slots = slots
-                .iter()
-                .map(|slot| slot.as_str())
-                .collect::<Vec<&str>>();
-            count_arrangements(&slots, groups, &mut HashMap::new())
-        })
+        .map(|record| record.unfold(5).arrangements())
         .sum()
 }
 
@@ -174,23 +191,32 @@ mod test {
 
     #[test]
     fn sample1() {
-        assert_eq!(part1(INPUT.lines().nth(0).unwrap()), 1);
-        assert_eq!(part1(INPUT.lines().nth(1).unwrap()), 4);
-        assert_eq!(part1(INPUT.lines().nth(2).unwrap()), 1);
-        assert_eq!(part1(INPUT.lines().nth(3).unwrap()), 1);
-        assert_eq!(part1(INPUT.lines().nth(4).unwrap()), 4);
-        assert_eq!(part1(INPUT.lines().nth(5).unwrap()), 10);
-        assert_eq!(part1(INPUT), 21)
+        let records = parse(INPUT).unwrap();
+        assert_eq!(
+            records.iter().map(Record::arrangements).collect::<Vec<_>>(),
+            vec![1, 4, 1, 1, 4, 10]
+        );
+        assert_eq!(part1(&records), 21)
     }
 
     #[test]
     fn sample2() {
-        assert_eq!(part2(INPUT.lines().nth(0).unwrap()), 1);
-        assert_eq!(part2(INPUT.lines().nth(1).unwrap()), 16384);
-        assert_eq!(part2(INPUT.lines().nth(2).unwrap()), 1);
-        assert_eq!(part2(INPUT.lines().nth(3).unwrap()), 16);
-        assert_eq!(part2(INPUT.lines().nth(4).unwrap()), 2500);
-        assert_eq!(part2(INPUT.lines().nth(5).unwrap()), 506250);
-        assert_eq!(part2(INPUT), 525152)
+        let records = parse(INPUT).unwrap();
+        assert_eq!(
+            records
+                .iter()
+                .map(|record| record.unfold(5).arrangements())
+                .collect::<Vec<_>>(),
+            vec![1, 16384, 1, 16, 2500, 506250]
+        );
+        assert_eq!(part2(&records), 525152)
+    }
+
+    #[test]
+    fn bad_record() {
+        let err = parse("???.### 1,1,3\n.??.x??...?##. 1,1,3").unwrap_err();
+        assert_eq!(err.to_string(), "day12 line 2 col 5: unknown spring 'x'");
+        let err = parse("???.###").unwrap_err();
+        assert_eq!(err.to_string(), "day12 line 1 col 1: expected ' '");
     }
 }
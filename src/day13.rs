@@ -4,23 +4,54 @@ use aoc_runner_derive::aoc_generator;
 use std::collections::HashMap;
 use std::collections::HashSet;
 
+use crate::grid::Grid;
+use crate::parse::{lines, Line, ParseError};
+
+const DAY: u32 = 13;
+
+/// A pattern of ash and rocks, as both its rows and its columns.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pattern {
+    pub rows: Vec<String>,
+    pub cols: Vec<String>,
+}
+
+impl Pattern {
+    fn new(grid: Grid<char>) -> Pattern {
+        let strings = |grid: &Grid<char>| grid.rows().map(String::from_iter).collect();
+        Pattern {
+            rows: strings(&grid),
+            cols: strings(&grid.transpose()),
+        }
+    }
+}
+
+fn parse_pattern(block: Vec<Line<'_>>) -> Result<Pattern, ParseError> {
+    let grid = Grid::from_lines(block, "tile", |c| matches!(c, '#' | '.').then_some(c))?;
+    Ok(Pattern::new(grid))
+}
+
+/// Reads the patterns, separated by blank lines.
 #[aoc_generator(day13)]
-fn input_generator(input: &str) -> Vec<(Vec<String>, Vec<String>)> {
-    let lines = input.lines().chain([""]);
+pub fn parse(input: &str) -> Result<Vec<Pattern>, ParseError> {
     let mut patterns = Vec::new();
-    let mut rows = Vec::<String>::new();
-    for line in lines {
-        if line.is_empty() {
-            let cols = (0..rows[0].len())
-                .map(|i| rows.iter().map(|r| r.chars().nth(i).unwrap()).collect())
-                .collect();
-            patterns.push((rows, cols));
-            rows = Vec::new();
+    let mut block = Vec::new();
+    let mut lines = lines(DAY, input);
+    for line in lines.by_ref() {
+        if line.text.is_empty() {
+            if block.is_empty() {
+                return Err(line.error("empty pattern"));
+            }
+            patterns.push(parse_pattern(std::mem::take(&mut block))?);
         } else {
-            rows.push(line.to_string());
+            block.push(line);
         }
     }
-    patterns
+    if block.is_empty() {
+        lines.expect("pattern")?;
+    }
+    patterns.push(parse_pattern(block)?);
+    Ok(patterns)
 }
 
 fn inits<T: AsRef<str>>(line: &T) -> impl Iterator<Item = (usize, &str)> + '_ {
@@ -76,7 +107,7 @@ fn find_palindromes_part2<'a>(
         .collect::<HashSet<(usize, bool)>>()
 }
 
-fn find_mirror<T: AsRef<str>>(lines: &[T]) -> Option<usize> {
+pub fn find_mirror<T: AsRef<str>>(lines: &[T]) -> Option<usize> {
     lines
         .iter()
         .map(|line| {
@@ -92,7 +123,7 @@ fn find_mirror<T: AsRef<str>>(lines: &[T]) -> Option<usize> {
         .copied()
 }
 
-fn find_mirror_part2<T: AsRef<str>>(lines: &[T]) -> Option<usize> {
+pub fn find_mirror_part2<T: AsRef<str>>(lines: &[T]) -> Option<usize> {
     lines
         .iter()
         .map(|line| {
@@ -125,11 +156,12 @@ fn find_mirror_part2<T: AsRef<str>>(lines: &[T]) -> Option<usize> {
         .copied()
 }
 
+/// Summarizes the line of reflection of each pattern.
 #[aoc(day13, part1)]
-fn part1(input: &[(Vec<String>, Vec<String>)]) -> usize {
-    input
+pub fn part1(patterns: &[Pattern]) -> usize {
+    patterns
         .iter()
-        .map(|(rows, cols)| {
+        .map(|Pattern { rows, cols }| {
             let horizontal_mirror = find_mirror(rows).unwrap_or(0);
             let vertical_mirror = find_mirror(cols).unwrap_or(0);
             100 * vertical_mirror + horizontal_mirror
@@ -137,11 +169,12 @@ fn part1(input: &[(Vec<String>, Vec<String>)]) -> usize {
         .sum()
 }
 
+/// Summarizes the line of reflection of each pattern once its smudge is fixed.
 #[aoc(day13, part2)]
-fn part2(input: &[(Vec<String>, Vec<String>)]) -> usize {
-    input
+pub fn part2(patterns: &[Pattern]) -> usize {
+    patterns
         .iter()
-        .map(|(rows, cols)| {
+        .map(|Pattern { rows, cols }| {
             let horizontal_mirror = find_mirror_part2(rows).unwrap_or(0);
             let vertical_mirror = find_mirror_part2(cols).unwrap_or(0);
             100 * vertical_mirror + horizontal_mirror
@@ -188,11 +221,22 @@ mod test {
 
     #[test]
     fn sample1() {
-        assert_eq!(part1(&input_generator(INPUT)), 405);
+        assert_eq!(part1(&parse(INPUT).unwrap()), 405);
     }
 
     #[test]
     fn sample2() {
-        assert_eq!(part2(&input_generator(INPUT)), 400);
+        assert_eq!(part2(&parse(INPUT).unwrap()), 400);
+    }
+
+    #[test]
+    fn bad_pattern() {
+        let err = parse("#.#\n.#.\n\n\n#.#").unwrap_err();
+        assert_eq!(err.to_string(), "day13 line 4 col 1: empty pattern");
+        let err = parse("#.#\n.#").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "day13 line 2 col 1: expected row of length 3, found 2"
+        );
     }
 }
@@ -3,152 +3,111 @@ use aoc_runner_derive::aoc_generator;
 
 use std::collections::HashMap;
 
-#[derive(Clone)]
-enum Tile {
+use crate::grid::Grid;
+use crate::parse::ParseError;
+
+const DAY: u32 = 14;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Tile {
     Empty,
     Cube,
     Rock,
 }
 
-type Pos = (usize, usize);
-type Map = HashMap<Pos, Tile>;
-type Rock = Pos;
+/// The platform's fixed cube-shaped rocks and rounded rocks, with north at the top.
+pub type Platform = Grid<Tile>;
 
+/// Reads the platform of rounded and cube-shaped rocks.
 #[aoc_generator(day14)]
-fn input_generator(input: &str) -> (Map, Vec<Rock>, (usize, usize)) {
-    let mut map = HashMap::new();
-    let mut rocks = Vec::new();
-    let mut max_y = 0;
-    let mut max_x = 0;
-    for (y, line) in input.lines().enumerate() {
-        max_y = y;
-        for (x, c) in line.chars().enumerate() {
-            max_x = x;
-            match c {
-                '.' => {
-                    map.insert((x, y), Tile::Empty);
-                }
-                '#' => {
-                    map.insert((x, y), Tile::Cube);
-                }
-                'O' => {
-                    map.insert((x, y), Tile::Empty);
-                    rocks.push((x, y));
-                }
-                _ => panic!(),
-            }
-        }
-    }
-    (map, rocks, (max_x + 1, max_y + 1))
+pub fn parse(input: &str) -> Result<Platform, ParseError> {
+    Grid::parse(DAY, input, "tile", |c| match c {
+        '.' => Some(Tile::Empty),
+        '#' => Some(Tile::Cube),
+        'O' => Some(Tile::Rock),
+        _ => None,
+    })
 }
 
-#[aoc(day14, part1)]
-fn part1(input: &(Map, Vec<Rock>, (usize, usize))) -> usize {
-    let (mut map, mut rocks, (_width, height)) = input.to_owned();
-    for rock in rocks.iter_mut() {
-        tilt(&map, rock, |r| r.1 > 0, |(x, y)| (*x, *y - 1), |r| r.1 -= 1);
-        map.insert(*rock, Tile::Rock);
-    }
-    calculate_load(&rocks, height)
+pub fn calculate_load(platform: &Platform) -> usize {
+    platform
+        .rows()
+        .enumerate()
+        .map(|(y, row)| (platform.height() - y) * row.iter().filter(|&&t| t == Tile::Rock).count())
+        .sum()
 }
 
-fn calculate_load(rocks: &[Rock], height: usize) -> usize {
-    rocks.iter().map(|(_, y)| height - y).sum()
+#[allow(dead_code)]
+fn show_map(platform: &Platform) {
+    print!(
+        "{}",
+        platform.render(|tile| match tile {
+            Tile::Empty => '.',
+            Tile::Cube => '#',
+            Tile::Rock => 'O',
+        })
+    );
 }
 
-fn show_map(map: &Map, size: (usize, usize)) {
-    for y in 0..size.1 {
-        for x in 0..size.0 {
-            match map.get(&(x, y)).unwrap() {
-                Tile::Empty => print!("."),
-                Tile::Cube => print!("#"),
-                Tile::Rock => print!("O"),
+/// Rolls every rounded rock as far north as it will go.
+fn tilt(platform: &mut Platform) {
+    for x in 0..platform.width() {
+        let mut free = 0;
+        for y in 0..platform.height() {
+            match platform[(x, y)] {
+                Tile::Empty => {}
+                Tile::Cube => free = y + 1,
+                Tile::Rock => {
+                    platform[(x, y)] = Tile::Empty;
+                    platform[(x, free)] = Tile::Rock;
+                    free += 1;
+                }
             }
         }
-        println!();
     }
 }
 
-fn tilt<C: Fn(&Rock) -> bool, N: Fn(&Rock) -> Pos, M: Fn(&mut Rock)>(
-    map: &Map,
-    rock: &mut Rock,
-    cond: C,
-    next: N,
-    modify: M,
-) {
-    while cond(rock) {
-        match map.get(&next(rock)).unwrap() {
-            Tile::Empty => modify(rock),
-            _ => break,
-        }
-    }
+/// The load on the north beams after tilting the platform north.
+#[aoc(day14, part1)]
+pub fn part1(platform: &Platform) -> usize {
+    let mut platform = platform.clone();
+    tilt(&mut platform);
+    calculate_load(&platform)
 }
 
-fn spin_cycle(map: &mut Map, rocks: &mut [Rock], size: (usize, usize)) {
-    // North
-    rocks.sort_by(|(_, y1), (_, y2)| y1.cmp(y2));
-    for rock in rocks.iter_mut() {
-        map.insert(*rock, Tile::Empty);
-        tilt(map, rock, |r| r.1 > 0, |(x, y)| (*x, *y - 1), |r| r.1 -= 1);
-        map.insert(*rock, Tile::Rock);
-    }
-    // West
-    rocks.sort_by(|(x1, _), (x2, _)| x1.cmp(x2));
-    for rock in rocks.iter_mut() {
-        map.insert(*rock, Tile::Empty);
-        tilt(map, rock, |r| r.0 > 0, |(x, y)| (*x - 1, *y), |r| r.0 -= 1);
-        map.insert(*rock, Tile::Rock);
-    }
-    // South
-    rocks.sort_by(|(_, y1), (_, y2)| y2.cmp(y1));
-    for rock in rocks.iter_mut() {
-        map.insert(*rock, Tile::Empty);
-        tilt(
-            map,
-            rock,
-            |r| r.1 < size.1 - 1,
-            |(x, y)| (*x, *y + 1),
-            |r| r.1 += 1,
-        );
-        map.insert(*rock, Tile::Rock);
-    }
-    // East
-    rocks.sort_by(|(x1, _), (x2, _)| x2.cmp(x1));
-    for rock in rocks.iter_mut() {
-        map.insert(*rock, Tile::Empty);
-        tilt(
-            map,
-            rock,
-            |r| r.0 < size.0 - 1,
-            |(x, y)| (*x + 1, *y),
-            |r| r.0 += 1,
-        );
-        map.insert(*rock, Tile::Rock);
+/// Tilts north, west, south and east in turn, by rotating the next direction to the top.
+fn spin_cycle(platform: &Platform) -> Platform {
+    let mut platform = platform.clone();
+    for _ in 0..4 {
+        tilt(&mut platform);
+        platform = platform.rotate_clockwise();
     }
+    platform
 }
 
+/// The load on the north beams after a billion spin cycles.
 #[aoc(day14, part2)]
-fn part2(input: &(Map, Vec<Rock>, (usize, usize))) -> usize {
-    let (mut map, mut rocks, (width, height)) = input.to_owned();
-    let mut memory = HashMap::<Vec<Rock>, usize>::new();
+pub fn part2(platform: &Platform) -> usize {
+    let mut platform = platform.clone();
+    let mut memory = HashMap::<Platform, usize>::new();
     const GOAL: usize = 1_000_000_000;
     let mut remainder = None;
     for i in 1..=GOAL {
-        spin_cycle(&mut map, &mut rocks, (width, height));
-        if memory.contains_key(&rocks) {
-            let loop_length = i - memory.get(&rocks).unwrap();
+        platform = spin_cycle(&platform);
+        if let Some(previous) = memory.get(&platform) {
+            let loop_length = i - previous;
             remainder = Some((GOAL - i) % loop_length);
             break;
         } else {
-            memory.insert(rocks.clone(), i);
+            memory.insert(platform.clone(), i);
         }
     }
     if let Some(remainder) = remainder {
         for _ in 1..=remainder {
-            spin_cycle(&mut map, &mut rocks, (width, height));
+            platform = spin_cycle(&platform);
         }
     }
-    calculate_load(&rocks, height)
+    calculate_load(&platform)
 }
 
 #[cfg(test)]
@@ -167,7 +126,7 @@ O.#..O.#.#
 .......O..
 #....###..
 #OO..#....";
-        assert_eq!(part1(&input_generator(input)), 136);
+        assert_eq!(part1(&parse(input).unwrap()), 136);
     }
 
     #[test]
@@ -182,6 +141,17 @@ O.#..O.#.#
 .......O..
 #....###..
 #OO..#....";
-        assert_eq!(part2(&input_generator(input)), 64);
+        assert_eq!(part2(&parse(input).unwrap()), 64);
+    }
+
+    #[test]
+    fn bad_platform() {
+        let err = parse("O..#\n.O.@").unwrap_err();
+        assert_eq!(err.to_string(), "day14 line 2 col 4: unknown tile '@'");
+        let err = parse("O..#\n.O.").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "day14 line 2 col 1: expected row of length 4, found 3"
+        );
     }
 }
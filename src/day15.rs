@@ -1,18 +1,55 @@
 use aoc_runner_derive::aoc;
 use aoc_runner_derive::aoc_generator;
 
+use crate::parse::{lines, Line, ParseError};
+
+const DAY: u32 = 15;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operation {
+    Remove,
+    Insert(usize),
+}
+
+/// A step of the initialization sequence, keeping its text for hashing.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Step {
+    pub text: String,
+    pub label: String,
+    pub operation: Operation,
+}
+
+fn parse_step<'a>(line: &Line<'a>, step: &'a str) -> Result<Step, ParseError> {
+    if let Some((i, c)) = step.char_indices().find(|(_, c)| !c.is_ascii()) {
+        let snippet = &step[i..i + c.len_utf8()];
+        return Err(line.error_at(snippet, format!("non-ASCII character '{}'", c)));
+    }
+    let (label, operation) = match step.strip_suffix('-') {
+        Some(label) => (label, Operation::Remove),
+        None => {
+            let (label, focal_length) = line.split_once(step, "=")?;
+            (label, Operation::Insert(line.parse(focal_length)?))
+        }
+    };
+    Ok(Step {
+        text: step.to_owned(),
+        label: label.to_owned(),
+        operation,
+    })
+}
+
+/// Reads the comma-separated steps of the initialization sequence.
 #[aoc_generator(day15)]
-fn input_generator(input: &str) -> Vec<String> {
-    input
-        .lines()
-        .next()
-        .unwrap()
+pub fn parse(input: &str) -> Result<Vec<Step>, ParseError> {
+    let line = lines(DAY, input).expect("initialization sequence")?;
+    line.text
         .split(',')
-        .map(|s| s.to_owned())
+        .map(|step| parse_step(&line, step))
         .collect()
 }
 
-fn hash(string: &str) -> usize {
+/// The HASH of `string`, from 0 to 255. Panics unless `string` is ASCII, which `parse` checks.
+pub fn hash(string: &str) -> usize {
     string
         .as_ascii()
         .unwrap()
@@ -20,9 +57,10 @@ fn hash(string: &str) -> usize {
         .fold(0u8, |cur, c| cur.wrapping_add(c.to_u8()).wrapping_mul(17)) as usize
 }
 
+/// Sums the hash of every step.
 #[aoc(day15, part1)]
-fn part1(strings: &[String]) -> usize {
-    strings.iter().map(|s| hash(s)).sum()
+pub fn part1(steps: &[Step]) -> usize {
+    steps.iter().map(|step| hash(&step.text)).sum()
 }
 
 fn remove(map: &mut [Vec<(&str, usize)>], label: &str) {
@@ -41,6 +79,7 @@ fn insert<'a>(map: &mut [Vec<(&'a str, usize)>], label: &'a str, focal_length: u
     }
 }
 
+#[allow(dead_code)]
 fn print_boxes(map: &[Vec<(&str, usize)>]) {
     for (bin_index, bin) in map.iter().enumerate() {
         if !bin.is_empty() {
@@ -54,18 +93,13 @@ fn print_boxes(map: &[Vec<(&str, usize)>]) {
     println!();
 }
 
+/// The focusing power of the lenses once every step has run.
 #[aoc(day15, part2)]
-fn part2(strings: &[String]) -> usize {
+pub fn part2(steps: &[Step]) -> usize {
     let mut map = vec![vec![]; 256];
-    strings.iter().for_each(|s| {
-        if s.ends_with('-') {
-            let label = &s[..s.len() - 1];
-            remove(&mut map, label);
-        } else {
-            let (label, focal_length) = s.split_once('=').unwrap();
-            let focal_length = focal_length.parse().unwrap();
-            insert(&mut map, label, focal_length);
-        }
+    steps.iter().for_each(|step| match step.operation {
+        Operation::Remove => remove(&mut map, &step.label),
+        Operation::Insert(focal_length) => insert(&mut map, &step.label, focal_length),
     });
     map.iter()
         .enumerate()
@@ -88,11 +122,22 @@ mod test {
 
     #[test]
     fn sample1() {
-        assert_eq!(part1(&input_generator(INPUT)), 1320);
+        assert_eq!(part1(&parse(INPUT).unwrap()), 1320);
     }
 
     #[test]
     fn sample2() {
-        assert_eq!(part2(&input_generator(INPUT)), 145);
+        assert_eq!(part2(&parse(INPUT).unwrap()), 145);
+    }
+
+    #[test]
+    fn bad_step() {
+        let err = parse("rn=1,cm-,qp3").unwrap_err();
+        assert_eq!(err.to_string(), "day15 line 1 col 10: expected '='");
+        let err = parse("rn=1,cm=x").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "day15 line 1 col 9: cannot parse 'x': invalid digit found in string"
+        );
     }
 }
@@ -3,61 +3,81 @@ use aoc_runner_derive::aoc_generator;
 
 use std::cmp::max;
 
-struct Subset {
-    red: u32,
-    green: u32,
-    blue: u32,
+use crate::parse::{lines, Line, ParseError};
+
+const DAY: u32 = 2;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Subset {
+    pub red: u32,
+    pub green: u32,
+    pub blue: u32,
+}
+
+/// A game and the subsets of cubes revealed during it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Game {
+    pub id: u32,
+    pub subsets: Vec<Subset>,
 }
 
 impl Subset {
-    fn contains(&self, other: &Self) -> bool {
+    pub fn contains(&self, other: &Self) -> bool {
         self.red >= other.red && self.green >= other.green && self.blue >= other.blue
     }
 
-    fn power(&self) -> u32 {
+    pub fn power(&self) -> u32 {
         self.red * self.green * self.blue
     }
 }
 
+fn parse_subset<'a>(line: &Line<'a>, subset: &'a str) -> Result<Subset, ParseError> {
+    let (mut red, mut green, mut blue) = (0, 0, 0);
+    for count in subset.split(", ") {
+        match line.split_once(count, " ")? {
+            (c, "red") => red = line.parse(c)?,
+            (c, "green") => green = line.parse(c)?,
+            (c, "blue") => blue = line.parse(c)?,
+            (_, colour) => {
+                return Err(line.error_at(colour, format!("unknown colour '{}'", colour)))
+            }
+        }
+    }
+    Ok(Subset { red, green, blue })
+}
+
+fn parse_game(line: Line) -> Result<Game, ParseError> {
+    let (game, subsets) = line.split_once(line.text, ": ")?;
+    let id = line.parse(line.split_once(game, " ")?.1)?;
+    let subsets = subsets
+        .split("; ")
+        .map(|subset| parse_subset(&line, subset))
+        .collect::<Result<_, _>>()?;
+    Ok(Game { id, subsets })
+}
+
+/// Reads one game per line.
 #[aoc_generator(day2)]
-fn input_generator(input: &str) -> Vec<(u32, Vec<Subset>)> {
-    input
-        .lines()
-        .map(|line| {
-            let (game, subsets) = line.split_once(": ").unwrap();
-            let game: u32 = game.split_once(' ').unwrap().1.parse().unwrap();
-            let subsets = subsets
-                .split("; ")
-                .map(|subset| {
-                    let (mut red, mut green, mut blue) = (0, 0, 0);
-                    for count in subset.split(", ") {
-                        match count.split_once(' ').unwrap() {
-                            (c, "red") => red = c.parse().unwrap(),
-                            (c, "green") => green = c.parse().unwrap(),
-                            (c, "blue") => blue = c.parse().unwrap(),
-                            _ => panic!("Not matched: {}", count),
-                        }
-                    }
-                    Subset { red, green, blue }
-                })
-                .collect();
-            (game, subsets)
-        })
-        .collect()
+pub fn parse(input: &str) -> Result<Vec<Game>, ParseError> {
+    lines(DAY, input).map(parse_game).collect()
 }
 
-fn union(sets: &Vec<Subset>) -> Subset {
-    let (mut red, mut green, mut blue) = (0, 0, 0);
-    for set in sets {
-        red = max(red, set.red);
-        green = max(green, set.green);
-        blue = max(blue, set.blue);
+impl Game {
+    /// The smallest set of cubes that could have produced every subset of this game.
+    pub fn union(&self) -> Subset {
+        let (mut red, mut green, mut blue) = (0, 0, 0);
+        for set in &self.subsets {
+            red = max(red, set.red);
+            green = max(green, set.green);
+            blue = max(blue, set.blue);
+        }
+        Subset { red, green, blue }
     }
-    Subset { red, green, blue }
 }
 
+/// Sums the ids of the games possible with 12 red, 13 green and 14 blue cubes.
 #[aoc(day2, part1)]
-fn part1(games: &[(u32, Vec<Subset>)]) -> u32 {
+pub fn part1(games: &[Game]) -> u32 {
     let actual = Subset {
         red: 12,
         green: 13,
@@ -65,17 +85,15 @@ fn part1(games: &[(u32, Vec<Subset>)]) -> u32 {
     };
     games
         .iter()
-        .filter(|(_, subsets)| actual.contains(&union(subsets)))
-        .map(|(game, _)| game)
+        .filter(|game| actual.contains(&game.union()))
+        .map(|game| game.id)
         .sum()
 }
 
+/// Sums the power of the fewest cubes each game could have been played with.
 #[aoc(day2, part2)]
-fn part2(games: &[(u32, Vec<Subset>)]) -> u32 {
-    games
-        .iter()
-        .map(|(_game, subsets)| union(subsets).power())
-        .sum()
+pub fn part2(games: &[Game]) -> u32 {
+    games.iter().map(|game| game.union().power()).sum()
 }
 
 #[cfg(test)]
@@ -90,11 +108,20 @@ Game 5: 6 red, 1 blue, 3 green; 2 blue, 1 red, 2 green";
 
     #[test]
     fn sample1() {
-        assert_eq!(part1(&input_generator(INPUT)), 8)
+        assert_eq!(part1(&parse(INPUT).unwrap()), 8)
     }
 
     #[test]
     fn sample2() {
-        assert_eq!(part2(&input_generator(INPUT)), 2286)
+        assert_eq!(part2(&parse(INPUT).unwrap()), 2286)
+    }
+
+    #[test]
+    fn bad_colour() {
+        let err = parse("Game 1: 3 blue, 4 red\nGame 2: 1 blue, 2 grey").err();
+        assert_eq!(
+            err.unwrap().to_string(),
+            "day2 line 2 col 19: unknown colour 'grey'"
+        );
     }
 }
@@ -1,23 +1,35 @@
 use aoc_runner_derive::aoc;
 use aoc_runner_derive::aoc_generator;
 
-#[derive(Debug)]
-struct Symbol {
-    x: u32,
-    y: u32,
-    c: char,
+use crate::grid::Grid;
+use crate::parse::ParseError;
+
+const DAY: u32 = 3;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Symbol {
+    pub x: u32,
+    pub y: u32,
+    pub c: char,
 }
 
-#[derive(Debug)]
-struct Part {
-    y: u32,
-    start_x: u32,
-    end_x: u32,
-    value: u32,
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Part {
+    pub y: u32,
+    pub start_x: u32,
+    pub end_x: u32,
+    pub value: u32,
+}
+
+/// The part numbers and symbols of an engine schematic.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Schematic {
+    pub parts: Vec<Part>,
+    pub symbols: Vec<Symbol>,
 }
 
 impl Part {
-    fn adjacent(&self, symbol: &Symbol) -> bool {
+    pub fn adjacent(&self, symbol: &Symbol) -> bool {
         self.y - 1 <= symbol.y
             && symbol.y <= self.y + 1
             && self.start_x <= symbol.x
@@ -25,59 +37,52 @@ impl Part {
     }
 }
 
+/// Finds the part numbers and symbols in the engine schematic.
 #[aoc_generator(day3)]
-fn input_generator(input: &str) -> (Vec<Part>, Vec<Symbol>) {
+pub fn parse(input: &str) -> Result<Schematic, ParseError> {
+    let grid = Grid::parse(DAY, input, "character", |c| {
+        (!c.is_whitespace() && !c.is_control()).then_some(c)
+    })?;
     let mut parts = vec![];
     let mut symbols = vec![];
-    for (y, line) in input.lines().enumerate() {
-        let mut part = false;
-        let mut value = 0u32;
-        let mut start_x = 0u32;
-        let mut last_x = 0u32;
-        for (x, c) in line.chars().enumerate() {
-            last_x = x as u32;
+    for (y, row) in grid.rows().enumerate() {
+        let y = (y + 1) as u32;
+        let mut part = None;
+        for (x, &c) in row.iter().enumerate() {
+            let x = x as u32;
             if let Some(v) = c.to_digit(10) {
-                if part {
-                    value = value * 10 + v;
-                } else {
-                    start_x = x as u32;
-                    value = v;
-                    part = true;
-                }
-            } else {
-                if part {
-                    parts.push(Part {
-                        y: (y + 1) as u32,
-                        start_x,
-                        end_x: (x + 1) as u32,
-                        value,
-                    });
-                    part = false;
-                }
-                if c != '.' {
-                    symbols.push(Symbol {
-                        x: (x + 1) as u32,
-                        y: (y + 1) as u32,
-                        c,
-                    });
-                }
+                let (_, value) = part.get_or_insert((x, 0));
+                *value = *value * 10 + v;
+                continue;
+            }
+            if let Some((start_x, value)) = part.take() {
+                parts.push(Part {
+                    y,
+                    start_x,
+                    end_x: x + 1,
+                    value,
+                });
+            }
+            if c != '.' {
+                symbols.push(Symbol { x: x + 1, y, c });
             }
         }
-        if part {
+        if let Some((start_x, value)) = part {
             parts.push(Part {
-                y: (y + 1) as u32,
+                y,
                 start_x,
-                end_x: last_x + 1,
+                end_x: row.len() as u32,
                 value,
             });
         }
     }
-    (parts, symbols)
+    Ok(Schematic { parts, symbols })
 }
 
+/// Sums the part numbers next to a symbol.
 #[aoc(day3, part1)]
-fn part1(stuff: &(Vec<Part>, Vec<Symbol>)) -> u32 {
-    let (parts, symbols) = stuff;
+pub fn part1(schematic: &Schematic) -> u32 {
+    let Schematic { parts, symbols } = schematic;
     parts
         .iter()
         .filter(|part| symbols.iter().any(|symbol| part.adjacent(symbol)))
@@ -85,9 +90,10 @@ fn part1(stuff: &(Vec<Part>, Vec<Symbol>)) -> u32 {
         .sum()
 }
 
+/// Sums the gear ratios of the `*` symbols next to exactly two part numbers.
 #[aoc(day3, part2)]
-fn part2(stuff: &(Vec<Part>, Vec<Symbol>)) -> u32 {
-    let (parts, symbols) = stuff;
+pub fn part2(schematic: &Schematic) -> u32 {
+    let Schematic { parts, symbols } = schematic;
     symbols
         .iter()
         .filter(|symbol| symbol.c == '*')
@@ -119,11 +125,20 @@ mod tests {
 
     #[test]
     fn sample1() {
-        assert_eq!(part1(&input_generator(INPUT)), 4361)
+        assert_eq!(part1(&parse(INPUT).unwrap()), 4361)
     }
 
     #[test]
     fn sample2() {
-        assert_eq!(part2(&input_generator(INPUT)), 467835)
+        assert_eq!(part2(&parse(INPUT).unwrap()), 467835)
+    }
+
+    #[test]
+    fn carriage_return() {
+        let err = parse("467..114..\r\r\n...*......").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "day3 line 1 col 11: unknown character '\\r'"
+        );
     }
 }
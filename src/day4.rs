@@ -4,47 +4,66 @@ use aoc_runner_derive::aoc_generator;
 use std::collections::HashMap;
 use std::collections::HashSet;
 
+use crate::parse::{lines, Line, ParseError};
+
+const DAY: u32 = 4;
+
+/// A scratchcard: its winning numbers and the numbers actually on it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Card {
+    pub winning: HashSet<u32>,
+    pub actual: HashSet<u32>,
+}
+
+impl Card {
+    pub fn matches(&self) -> usize {
+        self.winning.intersection(&self.actual).count()
+    }
+}
+
+fn parse_numbers<'a>(line: &Line<'a>, numbers: &'a str) -> Result<HashSet<u32>, ParseError> {
+    numbers.split_whitespace().map(|s| line.parse(s)).collect()
+}
+
+fn parse_card(line: Line) -> Result<Card, ParseError> {
+    let (card, numbers) = line.split_once(line.text, ":")?;
+    if !card.starts_with("Card") {
+        return Err(line.error_at(card, "expected 'Card'"));
+    }
+    let (winning, actual) = line.split_once(numbers, "|")?;
+    Ok(Card {
+        winning: parse_numbers(&line, winning)?,
+        actual: parse_numbers(&line, actual)?,
+    })
+}
+
+/// Reads one scratchcard per line.
 #[aoc_generator(day4)]
-fn input_generator(input: &str) -> Vec<(HashSet<u32>, HashSet<u32>)> {
-    input
-        .lines()
-        .map(|line| {
-            let (winning, actual) = line.split_once('|').unwrap();
-            let winning = winning
-                .split_whitespace()
-                .skip(2)
-                .map(|s| s.parse().unwrap())
-                .collect();
-            let actual = actual
-                .split_whitespace()
-                .map(|s| s.parse().unwrap())
-                .collect();
-            (winning, actual)
-        })
-        .collect()
+pub fn parse(input: &str) -> Result<Vec<Card>, ParseError> {
+    lines(DAY, input).map(parse_card).collect()
 }
 
+/// Sums the points of the cards, doubling for every match after the first.
 #[aoc(day4, part1)]
-fn part1(cards: &[(HashSet<u32>, HashSet<u32>)]) -> u32 {
+pub fn part1(cards: &[Card]) -> u32 {
     cards
         .iter()
-        .map(
-            |(winning, actual)| match winning.intersection(actual).count() {
-                0 => 0,
-                n => 2u32.pow((n - 1) as u32),
-            },
-        )
+        .map(|card| match card.matches() {
+            0 => 0,
+            n => 2u32.pow((n - 1) as u32),
+        })
         .sum()
 }
 
+/// Counts the cards held once every card has won copies of the cards after it.
 #[aoc(day4, part2)]
-fn part2(cards: &[(HashSet<u32>, HashSet<u32>)]) -> u32 {
+pub fn part2(cards: &[Card]) -> u32 {
     let mut counts = HashMap::<usize, u32>::new();
     cards
         .iter()
         .enumerate()
-        .map(|(cardnum, (winning, actual))| {
-            let matches = winning.intersection(actual).count();
+        .map(|(cardnum, card)| {
+            let matches = card.matches();
             let count = 1 + *counts.get(&cardnum).unwrap_or(&0);
             for n in cardnum + 1..=cardnum + matches {
                 counts.entry(n).and_modify(|c| *c += count).or_insert(count);
@@ -67,11 +86,17 @@ Card 6: 31 18 13 56 72 | 74 77 10 23 35 67 36 11";
 
     #[test]
     fn sample1() {
-        assert_eq!(part1(&input_generator(INPUT)), 13)
+        assert_eq!(part1(&parse(INPUT).unwrap()), 13)
     }
 
     #[test]
     fn sample2() {
-        assert_eq!(part2(&input_generator(INPUT)), 30)
+        assert_eq!(part2(&parse(INPUT).unwrap()), 30)
+    }
+
+    #[test]
+    fn bad_number() {
+        let err = parse("Card 1: 41 48 | 83 8x").unwrap_err();
+        assert_eq!((err.line, err.column, err.snippet.as_str()), (1, 20, "8x"));
     }
 }
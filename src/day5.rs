@@ -1,41 +1,135 @@
 use aoc_runner_derive::aoc;
 use aoc_runner_derive::aoc_generator;
 
-use std::collections::HashMap;
+use std::collections::BTreeMap;
+use std::collections::HashSet;
+use std::error::Error;
+use std::fmt;
+use std::fmt::Display;
 
-#[derive(Debug)]
-struct Range {
-    dst_start: u64,
-    src_start: u64,
-    len: u64,
+use crate::parse::{lines, Lines, ParseError};
+
+const DAY: u32 = 5;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Range {
+    pub dst_start: u64,
+    pub src_start: u64,
+    pub len: u64,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct SeedRange {
+    pub start: u64,
+    pub len: u64,
 }
 
-#[derive(Clone, Copy, PartialEq, Debug)]
-struct SeedRange {
-    start: u64,
-    len: u64,
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Map {
+    pub ranges: Vec<Range>,
 }
 
-#[derive(Debug)]
-struct Map {
-    ranges: Vec<Range>,
+/// The seeds to plant, and the maps between categories.
+///
+/// `maps` holds the map for each pair of source and destination categories, by source category
+/// first, so the categories form a directed graph.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Almanac {
+    pub seeds: Vec<u64>,
+    pub maps: BTreeMap<String, BTreeMap<String, Map>>,
+}
+
+#[derive(Clone, PartialEq, Eq)]
+pub enum PathError {
+    NoPath {
+        from: String,
+        to: String,
+    },
+    Cycle(Vec<String>),
+    Ambiguous {
+        from: String,
+        to: String,
+        split: String,
+    },
+    /// There are no seeds to follow to a location.
+    NoSeeds,
+}
+
+impl Display for PathError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PathError::NoPath { from, to } => write!(f, "no maps lead from {} to {}", from, to),
+            PathError::Cycle(cycle) => write!(f, "maps form a cycle: {}", cycle.join(" -> ")),
+            PathError::Ambiguous { from, to, split } => write!(
+                f,
+                "more than one path leads from {} to {}, splitting at {}",
+                from, to, split
+            ),
+            PathError::NoSeeds => write!(f, "no seeds to plant"),
+        }
+    }
+}
+
+/// Like [`ParseError`], shown by its message when a solver fails.
+impl fmt::Debug for PathError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        Display::fmt(self, f)
+    }
 }
 
+impl Error for PathError {}
+
 impl Range {
-    fn contains(&self, num: u64) -> bool {
-        self.src_start <= num && num < self.src_start + self.len
+    pub fn src_end(&self) -> u64 {
+        self.src_start + self.len
+    }
+
+    pub fn dst_end(&self) -> u64 {
+        self.dst_start + self.len
     }
 
-    fn convert(&self, num: u64) -> u64 {
+    pub fn contains(&self, num: u64) -> bool {
+        self.src_start <= num && num < self.src_end()
+    }
+
+    pub fn convert(&self, num: u64) -> u64 {
         num - self.src_start + self.dst_start
     }
+
+    /// The range converting the other way, from destination to source.
+    pub fn invert(&self) -> Range {
+        Range {
+            dst_start: self.src_start,
+            src_start: self.dst_start,
+            len: self.len,
+        }
+    }
 }
 
 impl SeedRange {
-    fn split_map(&self, range: &Range) -> (SeedRange, Option<SeedRange>) {
-        let last = self.start + self.len - 1;
-        let range_last = range.src_start + range.len;
-        if last <= range.src_start + range.len {
+    pub fn end(&self) -> u64 {
+        self.start + self.len
+    }
+
+    /// Splits the range into the part before `at` and the part from `at` onwards.
+    pub fn split_at(&self, at: u64) -> (SeedRange, SeedRange) {
+        let len = at - self.start;
+        (
+            SeedRange {
+                start: self.start,
+                len,
+            },
+            SeedRange {
+                start: at,
+                len: self.len - len,
+            },
+        )
+    }
+
+    /// Converts the part of the range covered by `range`, which must contain its start, and
+    /// returns the rest unconverted.
+    pub fn split_map(&self, range: &Range) -> (SeedRange, Option<SeedRange>) {
+        if self.end() <= range.src_end() {
             (
                 SeedRange {
                     start: range.convert(self.start),
@@ -44,23 +138,36 @@ impl SeedRange {
                 None,
             )
         } else {
-            let len = range_last - self.start;
+            let (first, rest) = self.split_at(range.src_end());
             (
                 SeedRange {
-                    start: range.convert(self.start),
-                    len,
+                    start: range.convert(first.start),
+                    len: first.len,
                 },
-                Some(SeedRange {
-                    start: self.start + len,
-                    len: self.len - len,
-                }),
+                Some(rest),
             )
         }
     }
 }
 
+/// Sorts the ranges and merges those that overlap or touch, leaving no empty ranges.
+pub fn normalize(mut ranges: Vec<SeedRange>) -> Vec<SeedRange> {
+    ranges.retain(|range| range.len > 0);
+    ranges.sort_by_key(|range| range.start);
+    let mut result: Vec<SeedRange> = Vec::with_capacity(ranges.len());
+    for range in ranges {
+        match result.last_mut() {
+            Some(last) if range.start <= last.end() => {
+                last.len = last.len.max(range.end() - last.start);
+            }
+            _ => result.push(range),
+        }
+    }
+    result
+}
+
 impl Map {
-    fn convert(&self, num: u64) -> u64 {
+    pub fn convert(&self, num: u64) -> u64 {
         for range in &self.ranges {
             if range.contains(num) {
                 return range.convert(num);
@@ -69,137 +176,443 @@ impl Map {
         num
     }
 
-    fn convert_range(&self, seeds: &SeedRange) -> Vec<SeedRange> {
+    /// The image of `seeds` under the map, as sorted, disjoint ranges.
+    ///
+    /// Parts of `seeds` not covered by any of the map's ranges keep their numbers.
+    pub fn convert_range(&self, seeds: &SeedRange) -> Vec<SeedRange> {
+        let mut ranges = self.ranges.iter().collect::<Vec<_>>();
+        ranges.sort_by_key(|range| range.src_start);
         let mut result = vec![];
-        for range in &self.ranges {
-            if range.contains(seeds.start) {
-                match seeds.split_map(range) {
-                    (first, Some(rem)) => {
-                        result.push(first);
-                        result.extend(self.convert_range(&rem));
-                    }
-                    (first, None) => {
-                        result.push(first);
+        let mut rest = Some(*seeds).filter(|seeds| seeds.len > 0);
+        for range in ranges {
+            let Some(seeds) = rest else {
+                break;
+            };
+            if range.src_end() <= seeds.start || range.len == 0 {
+                continue;
+            }
+            if seeds.end() <= range.src_start {
+                break;
+            }
+            let seeds = if seeds.start < range.src_start {
+                let (gap, seeds) = seeds.split_at(range.src_start);
+                result.push(gap);
+                seeds
+            } else {
+                seeds
+            };
+            let (mapped, unmapped) = seeds.split_map(range);
+            result.push(mapped);
+            rest = unmapped;
+        }
+        result.extend(rest);
+        normalize(result)
+    }
+
+    /// The image of all of `seeds` under the map, as sorted, disjoint ranges.
+    pub fn convert_ranges(&self, seeds: &[SeedRange]) -> Vec<SeedRange> {
+        normalize(
+            seeds
+                .iter()
+                .flat_map(|seeds| self.convert_range(seeds))
+                .collect(),
+        )
+    }
+
+    /// The numbers that convert into `range`, as sorted, disjoint ranges.
+    pub fn preimage(&self, range: &SeedRange) -> Vec<SeedRange> {
+        normalize(
+            self.pieces()
+                .iter()
+                .map(Range::invert)
+                .filter_map(|inverse| {
+                    let start = range.start.max(inverse.src_start);
+                    let end = range.end().min(inverse.src_end());
+                    (start < end).then(|| SeedRange {
+                        start: inverse.convert(start),
+                        len: end - start,
+                    })
+                })
+                .collect(),
+        )
+    }
+
+    pub fn preimage_ranges(&self, ranges: &[SeedRange]) -> Vec<SeedRange> {
+        normalize(
+            ranges
+                .iter()
+                .flat_map(|range| self.preimage(range))
+                .collect(),
+        )
+    }
+
+    /// The map's ranges in source order, with identity ranges filling the gaps between them, so
+    /// that together they cover every number.
+    fn pieces(&self) -> Vec<Range> {
+        let mut ranges = self.ranges.clone();
+        ranges.sort_by_key(|range| range.src_start);
+        let mut pieces = vec![];
+        let mut next = 0;
+        for range in ranges {
+            if next < range.src_start {
+                pieces.push(Range {
+                    dst_start: next,
+                    src_start: next,
+                    len: range.src_start - next,
+                });
+            }
+            next = next.max(range.src_end());
+            pieces.push(range);
+        }
+        pieces.push(Range {
+            dst_start: next,
+            src_start: next,
+            len: u64::MAX - next,
+        });
+        pieces
+    }
+
+    /// The map that converts like this one followed by `next`.
+    ///
+    /// The result has its ranges sorted by source, merges ranges that continue each other, and
+    /// leaves out ranges that map numbers to themselves.
+    pub fn then(&self, next: &Map) -> Map {
+        let next_pieces = next.pieces();
+        let mut ranges: Vec<Range> = vec![];
+        for piece in self.pieces() {
+            for next_piece in &next_pieces {
+                let start = piece.dst_start.max(next_piece.src_start);
+                let end = piece.dst_end().min(next_piece.src_end());
+                if start >= end {
+                    continue;
+                }
+                let range = Range {
+                    dst_start: next_piece.convert(start),
+                    src_start: piece.src_start + (start - piece.dst_start),
+                    len: end - start,
+                };
+                if range.dst_start == range.src_start {
+                    continue;
+                }
+                match ranges.last_mut() {
+                    Some(last)
+                        if last.src_end() == range.src_start
+                            && last.dst_end() == range.dst_start =>
+                    {
+                        last.len += range.len
                     }
+                    _ => ranges.push(range),
                 }
             }
         }
-        if result.is_empty() {
-            result.push(*seeds)
+        Map { ranges }
+    }
+}
+
+/// Writes the map in the almanac's own format, one `dst src len` range per line.
+impl Display for Map {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for range in &self.ranges {
+            writeln!(f, "{} {} {}", range.dst_start, range.src_start, range.len)?;
         }
-        result
+        Ok(())
     }
 }
 
-type Input = (Vec<u64>, HashMap<String, String>, HashMap<String, Map>);
+/// Something the maps can convert: a single number, or ranges of numbers.
+pub trait Convert {
+    type Output;
 
-fn split_map_name(map_name: &str) -> (&str, &str) {
-    let mut parts = map_name.split('-');
-    let src = parts.next().unwrap();
-    parts.next().unwrap();
-    let dst = parts.next().unwrap();
-    (src, dst)
+    fn convert_with(&self, map: &Map) -> Self::Output;
 }
 
-fn parse_map(lines: &mut dyn Iterator<Item = &str>) -> Map {
-    let ranges = lines
-        .take_while(|l| !l.is_empty())
-        .map(|l| {
-            let mut nums = l.split_whitespace().map(|w| w.parse().unwrap());
-            let dst_start = nums.next().unwrap();
-            let src_start = nums.next().unwrap();
-            let len = nums.next().unwrap();
-            Range {
-                dst_start,
-                src_start,
-                len,
+impl Convert for u64 {
+    type Output = u64;
+
+    fn convert_with(&self, map: &Map) -> u64 {
+        map.convert(*self)
+    }
+}
+
+impl Convert for SeedRange {
+    type Output = Vec<SeedRange>;
+
+    fn convert_with(&self, map: &Map) -> Vec<SeedRange> {
+        map.convert_range(self)
+    }
+}
+
+impl Convert for [SeedRange] {
+    type Output = Vec<SeedRange>;
+
+    fn convert_with(&self, map: &Map) -> Vec<SeedRange> {
+        map.convert_ranges(self)
+    }
+}
+
+impl Almanac {
+    /// The categories on the way from `from` to `to`, both included.
+    ///
+    /// Only the categories that lie between `from` and `to` matter: maps elsewhere in the
+    /// almanac may branch or loop freely. Fails if no maps lead there, if the maps split into
+    /// more than one path, or if they loop somewhere on the way, which would allow endlessly
+    /// many paths.
+    pub fn path<'a>(&'a self, from: &'a str, to: &str) -> Result<Vec<&'a str>, PathError> {
+        let mut targets = BTreeMap::<&str, Vec<&str>>::new();
+        let mut sources = BTreeMap::<&str, Vec<&str>>::new();
+        for (src, dst) in self
+            .maps
+            .iter()
+            .flat_map(|(src, maps)| maps.keys().map(move |dst| (src, dst)))
+        {
+            targets.entry(src).or_default().push(dst);
+            sources.entry(dst).or_default().push(src);
+        }
+        let ahead = reachable(from, &targets);
+        let behind = reachable(to, &sources);
+        if !ahead.contains(to) {
+            return Err(PathError::NoPath {
+                from: from.to_string(),
+                to: to.to_string(),
+            });
+        }
+        let mut path = vec![from];
+        loop {
+            let category = path[path.len() - 1];
+            if category == to {
+                return Ok(path);
             }
-        })
-        .collect();
-    Map { ranges }
+            let next = targets
+                .get(category)
+                .into_iter()
+                .flatten()
+                .filter(|&&next| behind.contains(next))
+                .collect::<Vec<_>>();
+            for &&next in &next {
+                if let Some(i) = path.iter().position(|&c| c == next) {
+                    let cycle = path[i..].iter().chain([&next]);
+                    return Err(PathError::Cycle(cycle.map(|c| c.to_string()).collect()));
+                }
+            }
+            match next[..] {
+                [&next] => path.push(next),
+                _ => {
+                    return Err(PathError::Ambiguous {
+                        from: from.to_string(),
+                        to: to.to_string(),
+                        split: category.to_string(),
+                    })
+                }
+            }
+        }
+    }
+
+    /// The maps leading from `from` to `to`, in order.
+    fn chain(&self, from: &str, to: &str) -> Result<Vec<&Map>, PathError> {
+        Ok(self
+            .path(from, to)?
+            .windows(2)
+            .map(|step| &self.maps[step[0]][step[1]])
+            .collect())
+    }
+
+    /// The single map converting `from` numbers to `to` numbers, failing as [`Almanac::path`]
+    /// does.
+    pub fn compose(&self, from: &str, to: &str) -> Result<Map, PathError> {
+        let maps = self.chain(from, to)?;
+        Ok(maps
+            .into_iter()
+            .fold(Map { ranges: vec![] }, |map, next| map.then(next)))
+    }
+
+    pub fn convert<T: Convert + ?Sized>(
+        &self,
+        from: &str,
+        to: &str,
+        value: &T,
+    ) -> Result<T::Output, PathError> {
+        Ok(value.convert_with(&self.compose(from, to)?))
+    }
+
+    /// The `from` numbers that end up as `to` numbers in `ranges`, walking the chain backwards.
+    pub fn preimage(
+        &self,
+        from: &str,
+        to: &str,
+        ranges: &[SeedRange],
+    ) -> Result<Vec<SeedRange>, PathError> {
+        let maps = self.chain(from, to)?;
+        Ok(maps
+            .into_iter()
+            .rev()
+            .fold(normalize(ranges.to_vec()), |ranges, map| {
+                map.preimage_ranges(&ranges)
+            }))
+    }
+
+    pub fn seed_ranges(&self) -> Vec<SeedRange> {
+        self.seeds
+            .chunks_exact(2)
+            .map(|c| SeedRange {
+                start: c[0],
+                len: c[1],
+            })
+            .collect()
+    }
+}
+
+/// The categories reachable from `start` along `edges`, `start` included.
+fn reachable<'a>(start: &'a str, edges: &BTreeMap<&'a str, Vec<&'a str>>) -> HashSet<&'a str> {
+    let mut seen = HashSet::from([start]);
+    let mut todo = vec![start];
+    while let Some(category) = todo.pop() {
+        for &next in edges.get(category).into_iter().flatten() {
+            if seen.insert(next) {
+                todo.push(next);
+            }
+        }
+    }
+    seen
 }
 
+/// Parses the ranges of a map, which must not overlap in their sources.
+fn parse_map(lines: &mut Lines) -> Result<Map, ParseError> {
+    let mut ranges = vec![];
+    for line in lines.by_ref() {
+        if line.text.is_empty() {
+            break;
+        }
+        let mut nums = line.text.split_whitespace();
+        let dst_start: u64 = line.parse(line.next(&mut nums, "destination range start")?)?;
+        let src_field = line.next(&mut nums, "source range start")?;
+        let src_start: u64 = line.parse(src_field)?;
+        let len_field = line.next(&mut nums, "range length")?;
+        let len = line.parse(len_field)?;
+        if let Some(extra) = nums.next() {
+            return Err(line.error_at(extra, format!("unexpected '{}'", extra)));
+        }
+        if dst_start.checked_add(len).is_none() || src_start.checked_add(len).is_none() {
+            return Err(line.error_at(len_field, "range runs past the largest number"));
+        }
+        let range = Range {
+            dst_start,
+            src_start,
+            len,
+        };
+        ranges.push((range, line, src_field));
+    }
+    let mut sorted = ranges
+        .iter()
+        .filter(|(range, ..)| range.len > 0)
+        .collect::<Vec<_>>();
+    sorted.sort_by_key(|(range, ..)| range.src_start);
+    for pair in sorted.windows(2) {
+        let (first, first_line, first_src) = pair[0];
+        let (second, second_line, second_src) = pair[1];
+        if second.src_start < first.src_end() {
+            let (line, src, other) = if first_line.number < second_line.number {
+                (second_line, second_src, first_line)
+            } else {
+                (first_line, first_src, second_line)
+            };
+            return Err(line.error_at(
+                src,
+                format!("source range overlaps the one on line {}", other.number),
+            ));
+        }
+    }
+    Ok(Map {
+        ranges: ranges.into_iter().map(|(range, ..)| range).collect(),
+    })
+}
+
+/// Reads the seeds and the maps between categories.
 #[aoc_generator(day5)]
-fn input_generator(input: &str) -> Input {
-    let mut lines = input.lines();
-    let seeds = lines
-        .next()
-        .unwrap()
-        .split_whitespace()
-        .skip(1)
-        .map(|s| s.parse().unwrap())
-        .collect();
-    let mut maps = HashMap::<String, Map>::new();
-    let mut names = HashMap::<String, String>::new();
-    lines.next();
-    while let Some(map_name) = lines.next() {
-        let (map_name, _) = map_name.split_once(' ').unwrap();
-        let (src_name, dst_name) = split_map_name(map_name);
-        let map = parse_map(&mut lines);
-        names.insert(src_name.to_string(), dst_name.to_string());
-        maps.insert(dst_name.to_string(), map);
-    }
-    (seeds, names, maps)
+pub fn parse(input: &str) -> Result<Almanac, ParseError> {
+    let mut lines = lines(DAY, input);
+    let line = lines.expect("seeds")?;
+    let (label, seeds) = line.split_once(line.text, ":")?;
+    if label != "seeds" {
+        return Err(line.error_at(label, "expected 'seeds'"));
+    }
+    let fields = seeds.split_whitespace().collect::<Vec<_>>();
+    if fields.is_empty() {
+        return Err(line.error_at(line.end(), "expected seed numbers"));
+    }
+    let numbers = fields
+        .iter()
+        .map(|s| line.parse(s))
+        .collect::<Result<Vec<_>, _>>()?;
+    if numbers.len() % 2 != 0 {
+        return Err(line.error_at(
+            seeds.trim_start(),
+            format!("expected pairs of seed numbers, found {}", numbers.len()),
+        ));
+    }
+    if let Some(i) = (1..numbers.len()).step_by(2).find(|&i| numbers[i] == 0) {
+        return Err(line.error_at(fields[i], "seed range of length 0"));
+    }
+    let seeds = numbers;
+    let mut maps = BTreeMap::<String, BTreeMap<String, Map>>::new();
+    lines.expect_blank()?;
+    while let Some(line) = lines.next() {
+        let (map_name, _) = line.split_once(line.text, " map:")?;
+        let (src_name, dst_name) = line.split_once(map_name, "-to-")?;
+        let map = parse_map(&mut lines)?;
+        let dst_maps = maps.entry(src_name.to_string()).or_default();
+        if dst_maps.insert(dst_name.to_string(), map).is_some() {
+            return Err(line.error_at(map_name, format!("duplicate map '{}'", map_name)));
+        }
+    }
+    Ok(Almanac { seeds, maps })
 }
 
+/// The lowest location of any seed.
 #[aoc(day5, part1)]
-fn part1(stuff: &Input) -> u64 {
-    let (seeds, names, maps) = stuff;
-    seeds
+pub fn part1(almanac: &Almanac) -> Result<u64, PathError> {
+    let map = almanac.compose("seed", "location")?;
+    almanac
+        .seeds
         .iter()
-        .map(|seed| {
-            let mut src_name = "seed";
-            let mut number = *seed;
-            loop {
-                let dst_name = names.get(src_name).unwrap();
-                let map = maps.get(dst_name).unwrap();
-                number = map.convert(number);
-                src_name = dst_name;
-                if src_name == "location" {
-                    break;
-                }
-            }
-            number
-        })
+        .map(|&seed| map.convert(seed))
         .min()
-        .unwrap()
+        .ok_or(PathError::NoSeeds)
 }
 
+/// The lowest location of any seed in the seed ranges.
 #[aoc(day5, part2)]
-fn part2(stuff: &Input) -> u64 {
-    let (seeds, names, maps) = stuff;
-    seeds
-        .chunks(2)
-        .map(|c| {
-            let start = *c.first().unwrap();
-            let len = *c.get(1).unwrap();
-            SeedRange { start, len }
-        })
-        .map(|seed_range| {
-            let mut src_name = "seed";
-            let mut ranges = vec![seed_range];
-            loop {
-                let dst_name = names.get(src_name).unwrap();
-                let map = maps.get(dst_name).unwrap();
-                let mut new_ranges = vec![];
-                for range in &ranges {
-                    let new = map.convert_range(range);
-                    new_ranges.extend(new);
-                }
-                ranges = new_ranges;
-                src_name = dst_name;
-                if src_name == "location" {
-                    break;
-                }
+pub fn part2(almanac: &Almanac) -> Result<u64, PathError> {
+    let seeds = almanac.seed_ranges();
+    let ranges = almanac.convert("seed", "location", seeds.as_slice())?;
+    ranges
+        .first()
+        .map(|range| range.start)
+        .ok_or(PathError::NoSeeds)
+}
+
+/// Searches the locations in increasing order for the first one that some seed leads to.
+#[aoc(day5, part2, inverse)]
+pub fn part2_inverse(almanac: &Almanac) -> Result<u64, PathError> {
+    let seeds = normalize(almanac.seed_ranges());
+    if seeds.is_empty() {
+        return Err(PathError::NoSeeds);
+    }
+    let map = almanac.compose("seed", "location")?;
+    let mut inverse = map.pieces().iter().map(Range::invert).collect::<Vec<_>>();
+    inverse.sort_by_key(|range| range.src_start);
+    let mut best = u64::MAX;
+    for range in inverse {
+        if range.src_start >= best {
+            break;
+        }
+        for seed in &seeds {
+            let start = seed.start.max(range.dst_start);
+            if start < seed.end().min(range.dst_end()) {
+                best = best.min(start - range.dst_start + range.src_start);
             }
-            *ranges
-                .iter()
-                .map(|SeedRange { start, .. }| start)
-                .min()
-                .unwrap()
-        })
-        .min()
-        .unwrap()
+        }
+    }
+    Ok(best)
 }
 
 #[cfg(test)]
@@ -242,7 +655,7 @@ humidity-to-location map:
 
     #[test]
     fn sample1() {
-        assert_eq!(part1(&input_generator(INPUT)), 35)
+        assert_eq!(part1(&parse(INPUT).unwrap()), Ok(35))
     }
 
     #[test]
@@ -257,8 +670,272 @@ humidity-to-location map:
         )
     }
 
+    #[test]
+    fn range_starting_in_gap() {
+        let map = Map {
+            ranges: vec![Range {
+                dst_start: 100,
+                src_start: 10,
+                len: 5,
+            }],
+        };
+        assert_eq!(
+            map.convert_range(&SeedRange { start: 5, len: 20 }),
+            vec![
+                SeedRange { start: 5, len: 5 },
+                SeedRange { start: 15, len: 10 },
+                SeedRange { start: 100, len: 5 },
+            ]
+        )
+    }
+
+    /// Every map of at most two disjoint ranges over a small domain, in both orders.
+    fn small_maps() -> Vec<Map> {
+        let mut ranges = vec![];
+        for src_start in 0..6 {
+            for len in 1..=3 {
+                for dst_start in [0, 3, 7] {
+                    ranges.push(Range {
+                        dst_start,
+                        src_start,
+                        len,
+                    });
+                }
+            }
+        }
+        let mut maps = vec![Map { ranges: vec![] }];
+        for a in &ranges {
+            maps.push(Map {
+                ranges: vec![a.clone()],
+            });
+            for b in &ranges {
+                if a.src_end() <= b.src_start || b.src_end() <= a.src_start {
+                    maps.push(Map {
+                        ranges: vec![a.clone(), b.clone()],
+                    });
+                }
+            }
+        }
+        maps
+    }
+
+    #[test]
+    fn convert_range_matches_convert() {
+        for map in small_maps() {
+            for start in 0..8 {
+                for len in 0..=6 {
+                    let seeds = SeedRange { start, len };
+                    let image = map.convert_range(&seeds);
+                    let mut expected = (start..start + len)
+                        .map(|n| map.convert(n))
+                        .collect::<Vec<_>>();
+                    expected.sort();
+                    expected.dedup();
+                    let actual = image
+                        .iter()
+                        .flat_map(|range| range.start..range.end())
+                        .collect::<Vec<_>>();
+                    assert_eq!(actual, expected, "{:?} {:?}", map, seeds);
+                    assert!(
+                        image.windows(2).all(|w| w[0].end() < w[1].start),
+                        "{:?} {:?}",
+                        map,
+                        seeds
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn composed_chain() {
+        let almanac = parse(INPUT).unwrap();
+        let map = almanac.compose("seed", "location").unwrap();
+        let path = almanac.path("seed", "location").unwrap();
+        assert_eq!(path.len(), 8);
+        for seed in 0..200 {
+            let mut number = seed;
+            for step in path.windows(2) {
+                number = almanac.maps[step[0]][step[1]].convert(number);
+            }
+            assert_eq!(map.convert(seed), number, "seed {}", seed);
+        }
+        let map = almanac.compose("temperature", "location").unwrap();
+        assert_eq!(
+            map.to_string(),
+            "1 0 55\n60 55 14\n0 69 1\n74 70 23\n56 93 4\n"
+        );
+        assert_eq!(
+            almanac.compose("location", "seed").unwrap_err().to_string(),
+            "no maps lead from location to seed"
+        );
+    }
+
+    #[test]
+    fn preimage_matches_convert() {
+        for map in small_maps() {
+            for start in 0..12 {
+                for len in 0..=4 {
+                    let range = SeedRange { start, len };
+                    let expected = (0..20)
+                        .filter(|&n| start <= map.convert(n) && map.convert(n) < range.end())
+                        .collect::<Vec<_>>();
+                    let actual = map
+                        .preimage(&range)
+                        .iter()
+                        .flat_map(|range| range.start..range.end().min(20))
+                        .collect::<Vec<_>>();
+                    assert_eq!(actual, expected, "{:?} {:?}", map, range);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn seeds_for_locations() {
+        let almanac = parse(INPUT).unwrap();
+        let below = [SeedRange { start: 0, len: 47 }];
+        let seeds = almanac.preimage("seed", "location", &below).unwrap();
+        let map = almanac.compose("seed", "location").unwrap();
+        for seed in 0..200 {
+            let found = seeds
+                .iter()
+                .any(|range| range.start <= seed && seed < range.end());
+            assert_eq!(found, map.convert(seed) < 47, "seed {}", seed);
+        }
+    }
+
     #[test]
     fn sample2() {
-        assert_eq!(part2(&input_generator(INPUT)), 46)
+        assert_eq!(part2(&parse(INPUT).unwrap()), Ok(46));
+        assert_eq!(part2_inverse(&parse(INPUT).unwrap()), Ok(46))
+    }
+
+    #[test]
+    fn category_graph() {
+        let almanac = parse(
+            "seeds: 1 2
+
+b-to-c map:
+100 20 10
+
+a-to-d map:
+50 0 10
+
+a-to-b map:
+20 0 5",
+        )
+        .unwrap();
+        assert_eq!(almanac.path("a", "c").unwrap(), vec!["a", "b", "c"]);
+        assert_eq!(almanac.convert("a", "c", &3u64).unwrap(), 103);
+        assert_eq!(almanac.convert("a", "d", &3u64).unwrap(), 53);
+        let range = SeedRange { start: 3, len: 4 };
+        assert_eq!(
+            almanac.convert("a", "b", &range).unwrap(),
+            vec![
+                SeedRange { start: 5, len: 2 },
+                SeedRange { start: 23, len: 2 }
+            ]
+        );
+        assert!(almanac.convert("d", "c", &3u64).is_err());
+        let cyclic = parse("seeds: 1 2\n\na-to-b map:\n\nb-to-a map:\n\nb-to-c map:\n").unwrap();
+        assert_eq!(cyclic.path("a", "b").unwrap(), vec!["a", "b"]);
+        assert_eq!(
+            cyclic.path("a", "c").unwrap_err(),
+            PathError::Cycle(vec!["a".into(), "b".into(), "a".into()])
+        );
+    }
+
+    #[test]
+    fn branches_and_loops() {
+        let almanac = parse(
+            "seeds: 1 2
+
+a-to-b map:
+
+b-to-c map:
+
+b-to-x map:
+
+x-to-x map:
+
+a-to-d map:
+
+d-to-c map:
+
+c-to-e map:
+
+e-to-c map:
+
+d-to-f map:
+
+f-to-d map:",
+        )
+        .unwrap();
+        assert_eq!(almanac.path("b", "c").unwrap(), vec!["b", "c"]);
+        assert_eq!(almanac.path("c", "e").unwrap(), vec!["c", "e"]);
+        assert_eq!(almanac.path("a", "a").unwrap(), vec!["a"]);
+        assert_eq!(
+            almanac.path("a", "c").unwrap_err().to_string(),
+            "more than one path leads from a to c, splitting at a"
+        );
+        assert_eq!(
+            format!("{:?}", almanac.path("a", "c").unwrap_err()),
+            "more than one path leads from a to c, splitting at a"
+        );
+        assert_eq!(
+            almanac.path("f", "c").unwrap_err(),
+            PathError::Cycle(vec!["f".into(), "d".into(), "f".into()])
+        );
+        assert_eq!(
+            part1(&parse("seeds: 1 2\n\nseed-to-soil map:\n").unwrap())
+                .unwrap_err()
+                .to_string(),
+            "no maps lead from seed to location"
+        );
+    }
+
+    #[test]
+    fn overlapping_ranges() {
+        let err = parse("seeds: 1 2\n\na-to-b map:\n10 0 5\n20 3 5").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "day5 line 5 col 4: source range overlaps the one on line 4"
+        );
+        let err = parse("seeds: 1 2\n\na-to-b map:\n\na-to-b map:\n").unwrap_err();
+        assert_eq!(err.to_string(), "day5 line 5 col 1: duplicate map 'a-to-b'");
+    }
+
+    #[test]
+    fn truncated_range() {
+        let err = parse(&INPUT[..INPUT.len() - 2]).unwrap_err();
+        assert_eq!(err.to_string(), "day5 line 33 col 6: missing range length");
+    }
+
+    #[test]
+    fn missing_seeds() {
+        let err = parse("seeds:\n").unwrap_err();
+        assert_eq!(err.to_string(), "day5 line 1 col 7: expected seed numbers");
+        let err = parse("seeds: 79 14 55 0\n").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "day5 line 1 col 17: seed range of length 0"
+        );
+        let almanac = Almanac {
+            seeds: vec![],
+            maps: parse(INPUT).unwrap().maps,
+        };
+        assert_eq!(part1(&almanac), Err(PathError::NoSeeds));
+        assert_eq!(part2(&almanac), Err(PathError::NoSeeds));
+        assert_eq!(part2_inverse(&almanac), Err(PathError::NoSeeds));
+    }
+
+    #[test]
+    fn odd_seed_count() {
+        let err = parse("seeds: 1 2 3\n").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "day5 line 1 col 8: expected pairs of seed numbers, found 3"
+        );
     }
 }
@@ -1,55 +1,229 @@
 use aoc_runner_derive::aoc;
 use aoc_runner_derive::aoc_generator;
 
-#[aoc_generator(day6, part1)]
-fn input_generator_part1(input: &str) -> Vec<(u64, u64)> {
-    let mut input = input
-        .lines()
-        .map(|line| line.split_whitespace().skip(1).map(|n| n.parse().unwrap()));
-    let durations = input.next().unwrap();
-    let records = input.next().unwrap();
-    durations.zip(records).collect()
+use std::ops::RangeInclusive;
+
+use crate::parse::{lines, Line, Lines, ParseError};
+
+const DAY: u32 = 6;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Race {
+    pub duration: u64,
+    pub record: u64,
+}
+
+/// The races on the sheet, plus the single race read by ignoring the spaces between numbers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Races {
+    pub races: Vec<Race>,
+    pub kerned: Race,
+}
+
+fn parse_row<'a>(lines: &mut Lines<'a>, label: &str) -> Result<(Line<'a>, &'a str), ParseError> {
+    let line = lines.expect(label)?;
+    let (name, values) = line.split_once(line.text, ":")?;
+    if name != label {
+        return Err(line.error_at(name, format!("expected '{}'", label)));
+    }
+    Ok((line, values))
+}
+
+fn parse_numbers<'a>(line: &Line<'a>, values: &'a str) -> Result<Vec<u64>, ParseError> {
+    values.split_whitespace().map(|n| line.parse(n)).collect()
+}
+
+fn parse_kerned<'a>(line: &Line<'a>, values: &'a str) -> Result<u64, ParseError> {
+    let digits = values.split_whitespace().collect::<String>();
+    digits.parse().map_err(|err| {
+        line.error_at(
+            values.trim_start(),
+            format!("cannot parse '{}': {}", digits, err),
+        )
+    })
+}
+
+/// Reads the race times and record distances, both as separate races and kerned into one.
+#[aoc_generator(day6)]
+pub fn parse(input: &str) -> Result<Races, ParseError> {
+    let mut lines = lines(DAY, input);
+    let (time_line, times) = parse_row(&mut lines, "Time")?;
+    let (distance_line, distances) = parse_row(&mut lines, "Distance")?;
+    let durations = parse_numbers(&time_line, times)?;
+    let records = parse_numbers(&distance_line, distances)?;
+    if durations.len() != records.len() {
+        return Err(distance_line.error(format!(
+            "expected {} distances, found {}",
+            durations.len(),
+            records.len()
+        )));
+    }
+    let races = durations
+        .into_iter()
+        .zip(records)
+        .map(|(duration, record)| Race { duration, record })
+        .collect();
+    let kerned = Race {
+        duration: parse_kerned(&time_line, times)?,
+        record: parse_kerned(&distance_line, distances)?,
+    };
+    Ok(Races { races, kerned })
+}
+
+/// How a boat moves: holding the button for a millisecond adds `acceleration` to its speed,
+/// starting from `initial_speed` and up to `max_speed`, and after releasing it the boat waits
+/// `cooldown` milliseconds before it starts moving.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RaceModel {
+    pub acceleration: u64,
+    pub initial_speed: u64,
+    pub cooldown: u64,
+    pub max_speed: Option<u64>,
+}
+
+/// The boats from the puzzle: one millimetre per millisecond faster for every millisecond held.
+impl Default for RaceModel {
+    fn default() -> RaceModel {
+        RaceModel {
+            acceleration: 1,
+            initial_speed: 0,
+            cooldown: 0,
+            max_speed: None,
+        }
+    }
+}
+
+impl RaceModel {
+    pub fn speed(&self, hold: u64) -> u128 {
+        let speed = self.initial_speed as u128 + self.acceleration as u128 * hold as u128;
+        self.max_speed.map_or(speed, |max| speed.min(max as u128))
+    }
+
+    pub fn distance(&self, duration: u64, hold: u64) -> u128 {
+        let moving = duration.saturating_sub(hold).saturating_sub(self.cooldown);
+        self.speed(hold).saturating_mul(moving as u128)
+    }
+
+    /// The hold times that beat the record, if any.
+    pub fn winning_holds(&self, race: &Race) -> Option<RangeInclusive<u64>> {
+        if self.max_speed.is_none() && self.acceleration > 0 {
+            self.solve_quadratic(race)
+        } else {
+            self.search(race)
+        }
+    }
+
+    pub fn count_ways_to_win(&self, race: &Race) -> u64 {
+        self.winning_holds(race)
+            .map_or(0, |holds| holds.end() - holds.start() + 1)
+    }
+
+    fn wins(&self, race: &Race, hold: u64) -> bool {
+        self.distance(race.duration, hold) > race.record as u128
+    }
+
+    /// Solves `(v + a h) (l - h) > r` for the hold time `h`, where `l` is the time left for
+    /// moving, exactly in integers.
+    fn solve_quadratic(&self, race: &Race) -> Option<RangeInclusive<u64>> {
+        let (a, v) = (self.acceleration as i128, self.initial_speed as i128);
+        let l = race.duration.saturating_sub(self.cooldown) as i128;
+        let r = race.record as i128;
+        let terms = || {
+            let al = a.checked_mul(l)?;
+            let discriminant = (al.checked_add(v)?)
+                .checked_pow(2)?
+                .checked_sub(a.checked_mul(4)?.checked_mul(r)?)?;
+            Some((al - v, discriminant))
+        };
+        let Some((b, discriminant)) = terms() else {
+            return self.search(race);
+        };
+        if discriminant < 0 {
+            return None;
+        }
+        // The integer square root puts both guesses within a step of the roots.
+        let root = discriminant.isqrt();
+        let guess = |numerator: i128| numerator.div_euclid(2 * a).clamp(0, l) as u64;
+        let (mut first, mut last) = (guess(b - root), guess(b + root));
+        while first > 0 && self.wins(race, first - 1) {
+            first -= 1;
+        }
+        while first <= last && !self.wins(race, first) {
+            first += 1;
+        }
+        if first > last {
+            return None;
+        }
+        while last < l as u64 && self.wins(race, last + 1) {
+            last += 1;
+        }
+        while !self.wins(race, last) {
+            last -= 1;
+        }
+        Some(first..=last)
+    }
+
+    /// Finds the winning hold times by binary search, relying only on the distance first rising
+    /// and then falling as the hold time grows.
+    fn search(&self, race: &Race) -> Option<RangeInclusive<u64>> {
+        let distance = |hold| self.distance(race.duration, hold);
+        let (mut low, mut high) = (0, race.duration);
+        while low < high {
+            let mid = low + (high - low) / 2;
+            if distance(mid) < distance(mid + 1) {
+                low = mid + 1;
+            } else {
+                high = mid;
+            }
+        }
+        let peak = low;
+        if !self.wins(race, peak) {
+            return None;
+        }
+        // Holding for the whole race never wins, as the boat has no time left to move.
+        let first = partition_point(0, peak, |hold| !self.wins(race, hold));
+        let last = partition_point(peak, race.duration, |hold| self.wins(race, hold)) - 1;
+        Some(first..=last)
+    }
+}
+
+/// The first number in `low..=high` for which `pred` is false, given that `pred` holds for a
+/// prefix of the range and not for `high`.
+fn partition_point<P: Fn(u64) -> bool>(mut low: u64, mut high: u64, pred: P) -> u64 {
+    while low < high {
+        let mid = low + (high - low) / 2;
+        if pred(mid) {
+            low = mid + 1;
+        } else {
+            high = mid;
+        }
+    }
+    low
 }
 
-#[aoc_generator(day6, part2)]
-fn input_generator_part2(input: &str) -> (u64, u64) {
-    let mut input = input.lines().map(|line| {
-        line.split_whitespace()
-            .skip(1)
-            .collect::<String>()
-            .parse()
-            .unwrap()
-    });
-    let duration = input.next().unwrap();
-    let record = input.next().unwrap();
-    (duration, record)
+pub fn winning_holds(race: &Race) -> Option<RangeInclusive<u64>> {
+    RaceModel::default().winning_holds(race)
 }
 
-fn count_ways_to_win(race: &(u64, u64)) -> u64 {
-    let (duration, record) = (race.0 as f64, race.1 as f64);
-    let diff = (duration * duration / 4.0 - record).sqrt();
-    let minf = duration / 2.0 - diff;
-    let maxf = duration / 2.0 + diff;
-    let min = minf.ceil() as u64;
-    let max = maxf.floor() as u64;
-    let mut count = max - min + 1;
-    if minf == (minf as u64) as f64 {
-        count -= 1;
-    }
-    if maxf == (maxf as u64) as f64 {
-        count -= 1;
-    }
-    count
+pub fn count_ways_to_win(race: &Race) -> u64 {
+    RaceModel::default().count_ways_to_win(race)
 }
 
+/// Multiplies the number of ways to beat the record in each race.
 #[aoc(day6, part1)]
-fn part1(races: &[(u64, u64)]) -> u64 {
-    races.iter().map(count_ways_to_win).product()
+pub fn part1(races: &Races) -> u64 {
+    let model = RaceModel::default();
+    races
+        .races
+        .iter()
+        .map(|race| model.count_ways_to_win(race))
+        .product()
 }
 
+/// The number of ways to beat the record in the single kerned race.
 #[aoc(day6, part2)]
-fn part2(race: &(u64, u64)) -> u64 {
-    count_ways_to_win(race)
+pub fn part2(races: &Races) -> u64 {
+    RaceModel::default().count_ways_to_win(&races.kerned)
 }
 
 #[cfg(test)]
@@ -61,11 +235,91 @@ Distance:  9  40  200";
 
     #[test]
     fn sample1() {
-        assert_eq!(part1(&input_generator_part1(INPUT)), 288)
+        assert_eq!(part1(&parse(INPUT).unwrap()), 288)
     }
 
     #[test]
     fn sample2() {
-        assert_eq!(part2(&input_generator_part2(INPUT)), 71503)
+        assert_eq!(part2(&parse(INPUT).unwrap()), 71503)
+    }
+
+    #[test]
+    fn winning_interval() {
+        let race = |duration, record| Race { duration, record };
+        assert_eq!(winning_holds(&race(7, 9)), Some(2..=5));
+        assert_eq!(winning_holds(&race(30, 200)), Some(11..=19));
+        assert_eq!(winning_holds(&race(30, 225)), None);
+        assert_eq!(winning_holds(&race(30, 224)), Some(15..=15));
+        assert_eq!(count_ways_to_win(&race(0, 0)), 0);
+    }
+
+    #[test]
+    fn beyond_float_precision() {
+        for duration in [(1 << 33) - 1, (1 << 53) + 1, u64::MAX] {
+            for hold in [1, 1000, 12345, duration / 3] {
+                // Holding for exactly `hold` only ties the record.
+                let Ok(record) = u64::try_from(hold as u128 * (duration - hold) as u128) else {
+                    continue;
+                };
+                let holds = winning_holds(&Race { duration, record }).unwrap();
+                assert_eq!(holds, hold + 1..=duration - hold - 1, "{}", duration);
+                assert_eq!(
+                    count_ways_to_win(&Race { duration, record }),
+                    duration - 2 * hold - 1
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn race_models() {
+        let mut models = vec![];
+        for acceleration in 0..3 {
+            for initial_speed in 0..3 {
+                for cooldown in 0..3 {
+                    for max_speed in [None, Some(1), Some(4)] {
+                        models.push(RaceModel {
+                            acceleration,
+                            initial_speed,
+                            cooldown,
+                            max_speed,
+                        });
+                    }
+                }
+            }
+        }
+        for model in models {
+            for duration in 0..15 {
+                for record in 0..40 {
+                    let race = Race { duration, record };
+                    let holds = (0..=duration)
+                        .filter(|&hold| model.distance(duration, hold) > record as u128)
+                        .collect::<Vec<_>>();
+                    let expected = holds.first().map(|&first| first..=*holds.last().unwrap());
+                    assert_eq!(
+                        model.winning_holds(&race),
+                        expected,
+                        "{:?} {:?}",
+                        model,
+                        race
+                    );
+                    assert_eq!(model.search(&race), expected, "{:?} {:?}", model, race);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn bad_input() {
+        let err = parse("Time:      7  15   30").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "day6 line 2 col 1: unexpected end of input, expected Distance"
+        );
+        let err = parse("Time:      7  15   30\nDistance:  9  40").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "day6 line 2 col 1: expected 3 distances, found 2"
+        );
     }
 }
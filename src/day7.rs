@@ -2,228 +2,282 @@ use aoc_runner_derive::aoc;
 use aoc_runner_derive::aoc_generator;
 
 use std::cmp::Ordering;
+use std::cmp::Reverse;
 use std::collections::HashMap;
+use std::fmt;
+use std::fmt::Display;
 
-type Card = u32;
+use crate::parse::{lines, Line, ParseError};
 
+const DAY: u32 = 7;
+
+/// A card's strength under a rule set: its position in the rule set's card order.
+pub type Card = u32;
+
+/// A hand as written in the input, along with its bid.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RawHand {
+    pub cards: String,
+    pub bid: u32,
+}
+
+/// A hand classified under a rule set, ordered by its type and then card by card.
 #[derive(Debug, Clone, Eq)]
-struct Hand {
-    bid: u32,
-    cards: Vec<Card>,
-    hand_type: HandType,
+pub struct Hand {
+    pub bid: u32,
+    pub cards: Vec<Card>,
+    /// The position of the hand's type on its rule set's ladder of hand types.
+    pub hand_type: usize,
 }
 
-#[derive(Debug, PartialOrd, Ord, PartialEq, Eq, Clone, Copy)]
-enum HandType {
-    HighCard(),
-    OnePair(),
-    TwoPair(),
-    ThreeOfAKind(),
-    FullHouse(),
-    FourOfAKind(),
-    FiveOfAKind(),
+/// A kind of hand, described by the groups of equal cards it needs: a full house is `[3, 2]`.
+///
+/// A hand has the type if it holds groups at least as large as these, of distinct cards.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HandType {
+    pub name: String,
+    pub groups: Vec<u32>,
 }
 
-fn count_cards(cards: &Vec<Card>) -> HashMap<Card, u32> {
-    let mut counts = HashMap::new();
-    for &card in cards {
-        counts
-            .entry(card)
-            .and_modify(|count| {
-                *count += 1;
-            })
-            .or_insert(1);
-    }
-    counts
+/// How to play camel cards: the cards from weakest to strongest, which of them are wild, how
+/// many cards make a hand, and the hand types from weakest to strongest.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RuleSet {
+    pub cards: Vec<char>,
+    pub wildcards: Vec<char>,
+    pub hand_size: usize,
+    pub hand_types: Vec<HandType>,
 }
 
-fn reverse_counts(counts: &HashMap<Card, u32>) -> HashMap<u32, Vec<Card>> {
-    let mut result = HashMap::<u32, Vec<Card>>::new();
-    for (&card, &count) in counts {
-        result
-            .entry(count)
-            .and_modify(|vec| {
-                vec.push(card);
-            })
-            .or_insert(vec![card]);
+impl HandType {
+    pub fn new(name: &str, groups: &[u32]) -> HandType {
+        let mut groups = groups.to_vec();
+        groups.sort_by(|a, b| b.cmp(a));
+        HandType {
+            name: name.to_string(),
+            groups,
+        }
     }
-    result
-}
 
-mod part1 {
-    use super::*;
+    /// Whether group sizes, largest first, include this type's groups.
+    pub fn matches(&self, groups: &[u32]) -> bool {
+        self.groups.len() <= groups.len() && self.groups.iter().zip(groups).all(|(t, g)| t <= g)
+    }
+}
 
-    fn classify_hand(counts: &HashMap<u32, Vec<Card>>) -> HandType {
-        if counts.contains_key(&5) {
-            HandType::FiveOfAKind()
-        } else if counts.contains_key(&4) {
-            HandType::FourOfAKind()
-        } else if counts.contains_key(&3) && counts.contains_key(&2) {
-            HandType::FullHouse()
-        } else if counts.contains_key(&3) {
-            HandType::ThreeOfAKind()
-        } else if let Some(two) = counts.get(&2)
-            && two.len() == 2
-        {
-            HandType::TwoPair()
-        } else if counts.contains_key(&2) {
-            HandType::OnePair()
-        } else {
-            HandType::HighCard()
+impl RuleSet {
+    /// The rules of the first part: no wildcards, and jacks between tens and queens.
+    pub fn standard() -> RuleSet {
+        RuleSet {
+            cards: "23456789TJQKA".chars().collect(),
+            wildcards: vec![],
+            hand_size: 5,
+            hand_types: vec![
+                HandType::new("high card", &[1]),
+                HandType::new("one pair", &[2]),
+                HandType::new("two pair", &[2, 2]),
+                HandType::new("three of a kind", &[3]),
+                HandType::new("full house", &[3, 2]),
+                HandType::new("four of a kind", &[4]),
+                HandType::new("five of a kind", &[5]),
+            ],
         }
     }
 
-    impl Hand {
-        fn new(cards: Vec<Card>, bid: u32) -> Self {
-            let counts = count_cards(&cards);
-            let counts = reverse_counts(&counts);
-            let hand_type = classify_hand(&counts);
-            Self {
-                cards,
-                bid,
-                hand_type,
-            }
+    /// The rules of the second part: jokers are wild, and the weakest card.
+    pub fn jokers() -> RuleSet {
+        RuleSet {
+            cards: "J23456789TQKA".chars().collect(),
+            wildcards: vec!['J'],
+            ..RuleSet::standard()
         }
     }
 
-    impl PartialOrd for Hand {
-        fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-            Some(self.cmp(other))
-        }
+    pub fn card(&self, c: char) -> Option<Card> {
+        self.cards
+            .iter()
+            .position(|&card| card == c)
+            .map(|i| i as Card)
     }
 
-    impl PartialEq for Hand {
-        fn eq(&self, other: &Self) -> bool {
-            self.hand_type == other.hand_type && self.cards == other.cards
-        }
+    pub fn is_wild(&self, card: Card) -> bool {
+        self.wildcards.contains(&self.cards[card as usize])
     }
 
-    fn compare_cards(cards1: &[Card], cards2: &[Card]) -> Ordering {
-        let result = match (cards1.first(), cards2.first()) {
-            (Some(x), Some(y)) if x == y => compare_cards(&cards1[1..], &cards2[1..]),
-            (Some(x), Some(y)) if x > y => Ordering::Greater,
-            (Some(x), Some(y)) if x < y => Ordering::Less,
-            _ => Ordering::Equal,
-        };
-        result
+    fn hand_type(&self, groups: &[u32]) -> Option<usize> {
+        self.hand_types.iter().rposition(|t| t.matches(groups))
+    }
+
+    /// The strongest hand type the cards make, with wildcards joining whichever groups help most.
+    ///
+    /// Falls back to the weakest type if no type matches.
+    pub fn classify(&self, cards: &[Card]) -> usize {
+        self.substitute(cards).0
+    }
+
+    /// The strongest hand type the cards make, along with the cards with each wildcard replaced
+    /// by the card it stands in for.
+    pub fn substitute(&self, cards: &[Card]) -> (usize, Vec<Card>) {
+        let mut counts = count_cards(cards);
+        counts.retain(|&card, _| !self.is_wild(card));
+        let mut groups = counts.into_iter().collect::<Vec<_>>();
+        groups.sort_by_key(|&(card, n)| Reverse((n, card)));
+        // Jokers forming groups of their own pretend to be the strongest cards not in the hand.
+        let mut unused = (0..self.cards.len() as Card)
+            .rev()
+            .filter(|&card| !self.is_wild(card) && !cards.contains(&card));
+        let jokers = cards.iter().filter(|&&card| self.is_wild(card)).count();
+        let mut best = None;
+        let mut targets = vec![];
+        self.assign_jokers(&mut groups, jokers, 0, &mut targets, &mut unused, &mut best);
+        let (hand_type, targets) = best.unwrap_or((0, vec![]));
+        let mut targets = targets.into_iter();
+        let cards = cards
+            .iter()
+            .map(|&card| match self.is_wild(card) {
+                true => targets.next().unwrap_or(card),
+                false => card,
+            })
+            .collect();
+        (hand_type, cards)
     }
 
-    impl Ord for Hand {
-        fn cmp(&self, other: &Self) -> Ordering {
-            match self.hand_type.cmp(&other.hand_type) {
-                Ordering::Equal => compare_cards(&self.cards, &other.cards),
-                other => other,
+    /// Tries adding each remaining joker to the groups from `from` on, or to a new group, and
+    /// keeps the first assignment giving the strongest hand type.
+    fn assign_jokers<I: Iterator<Item = Card> + Clone>(
+        &self,
+        groups: &mut Vec<(Card, u32)>,
+        jokers: usize,
+        from: usize,
+        targets: &mut Vec<Card>,
+        unused: &mut I,
+        best: &mut Option<(usize, Vec<Card>)>,
+    ) {
+        if jokers == 0 {
+            let mut sizes = groups.iter().map(|&(_, n)| n).collect::<Vec<_>>();
+            sizes.sort_by(|a, b| b.cmp(a));
+            let hand_type = self.hand_type(&sizes).unwrap_or(0);
+            if best.as_ref().is_none_or(|(best, _)| hand_type > *best) {
+                *best = Some((hand_type, targets.clone()));
             }
+            return;
+        }
+        for i in from..groups.len() {
+            groups[i].1 += 1;
+            targets.push(groups[i].0);
+            self.assign_jokers(groups, jokers - 1, i, targets, unused, best);
+            targets.pop();
+            groups[i].1 -= 1;
+        }
+        let mut rest = unused.clone();
+        if let Some(card) = rest.next() {
+            groups.push((card, 1));
+            targets.push(card);
+            self.assign_jokers(
+                groups,
+                jokers - 1,
+                groups.len() - 1,
+                targets,
+                &mut rest,
+                best,
+            );
+            targets.pop();
+            groups.pop();
         }
     }
 
-    fn parse_card(c: char) -> Card {
-        match c {
-            'T' => 10,
-            'J' => 11,
-            'Q' => 12,
-            'K' => 13,
-            'A' => 14,
-            _ => c.to_digit(10).unwrap(),
+    /// The hand for cards that are valid under these rules.
+    pub fn hand(&self, raw: &RawHand) -> Hand {
+        let cards = raw
+            .cards
+            .chars()
+            .map(|c| self.card(c).unwrap())
+            .collect::<Vec<_>>();
+        Hand {
+            bid: raw.bid,
+            hand_type: self.classify(&cards),
+            cards,
         }
     }
+}
 
-    pub fn parse_hand(line: &str) -> Hand {
-        let (hand, bid) = line.split_once(' ').unwrap();
-        Hand::new(hand.chars().map(parse_card).collect(), bid.parse().unwrap())
+pub fn count_cards(cards: &[Card]) -> HashMap<Card, u32> {
+    let mut counts = HashMap::new();
+    for &card in cards {
+        *counts.entry(card).or_insert(0) += 1;
     }
+    counts
 }
 
-mod part2 {
-    use super::*;
+impl PartialOrd for Hand {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
 
-    fn classify_hand(counts: &HashMap<u32, Vec<Card>>, jokers: u32) -> HandType {
-        if jokers >= 4 || jokers == 3 && counts.contains_key(&2) {
-            HandType::FiveOfAKind()
-        } else if jokers == 3 {
-            HandType::FourOfAKind()
-        } else if jokers == 2 && counts.contains_key(&3) {
-            HandType::FiveOfAKind()
-        } else if jokers == 2 && counts.contains_key(&2) {
-            HandType::FourOfAKind()
-        } else if jokers == 2 {
-            HandType::ThreeOfAKind()
-        } else if jokers == 1 && counts.contains_key(&4) {
-            HandType::FiveOfAKind()
-        } else if jokers == 1 && counts.contains_key(&3) {
-            HandType::FourOfAKind()
-        } else if jokers == 1
-            && let Some(two) = counts.get(&2)
-            && two.len() == 2
-        {
-            HandType::FullHouse()
-        } else if jokers == 1 && counts.contains_key(&2) {
-            HandType::ThreeOfAKind()
-        } else if jokers == 1 {
-            HandType::OnePair()
-        } else if counts.contains_key(&5) {
-            HandType::FiveOfAKind()
-        } else if counts.contains_key(&4) {
-            HandType::FourOfAKind()
-        } else if counts.contains_key(&3) && counts.contains_key(&2) {
-            HandType::FullHouse()
-        } else if counts.contains_key(&3) {
-            HandType::ThreeOfAKind()
-        } else if let Some(two) = counts.get(&2)
-            && two.len() == 2
-        {
-            HandType::TwoPair()
-        } else if counts.contains_key(&2) {
-            HandType::OnePair()
-        } else {
-            HandType::HighCard()
-        }
+impl PartialEq for Hand {
+    fn eq(&self, other: &Self) -> bool {
+        self.hand_type == other.hand_type && self.cards == other.cards
     }
+}
 
-    impl Hand {
-        fn new_part2(cards: Vec<Card>, bid: u32) -> Self {
-            let mut counts = count_cards(&cards);
-            let jokers = counts.remove(&1).unwrap_or(0);
-            let counts = reverse_counts(&counts);
-            let hand_type = classify_hand(&counts, jokers);
-            Self {
-                cards,
-                bid,
-                hand_type,
-            }
-        }
+/// The first position at which the cards differ, which decides between hands of the same type.
+pub fn tie_break(cards1: &[Card], cards2: &[Card]) -> Option<usize> {
+    cards1.iter().zip(cards2).position(|(x, y)| x != y)
+}
+
+fn compare_cards(cards1: &[Card], cards2: &[Card]) -> Ordering {
+    match tie_break(cards1, cards2) {
+        Some(i) => cards1[i].cmp(&cards2[i]),
+        None => Ordering::Equal,
     }
+}
 
-    fn parse_card(c: char) -> Card {
-        match c {
-            'J' => 1,
-            'T' => 10,
-            'Q' => 12,
-            'K' => 13,
-            'A' => 14,
-            _ => c.to_digit(10).unwrap(),
+impl Ord for Hand {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match self.hand_type.cmp(&other.hand_type) {
+            Ordering::Equal => compare_cards(&self.cards, &other.cards),
+            other => other,
         }
     }
+}
 
-    pub fn parse_hand(line: &str) -> Hand {
-        let (hand, bid) = line.split_once(' ').unwrap();
-        Hand::new_part2(hand.chars().map(parse_card).collect(), bid.parse().unwrap())
+fn parse_hand(rules: &RuleSet, line: Line) -> Result<RawHand, ParseError> {
+    let (cards, bid) = line.split_once(line.text, " ")?;
+    if let Some((x, c)) = cards
+        .chars()
+        .enumerate()
+        .find(|&(_, c)| rules.card(c).is_none())
+    {
+        return Err(line.unknown_char(x, c, "card"));
     }
+    let count = cards.chars().count();
+    if count != rules.hand_size {
+        return Err(line.error_at(
+            cards,
+            format!("expected {} cards, found {}", rules.hand_size, count),
+        ));
+    }
+    Ok(RawHand {
+        cards: cards.to_string(),
+        bid: line.parse(bid)?,
+    })
 }
 
-#[aoc_generator(day7, part1)]
-fn input_generator(input: &str) -> Vec<Hand> {
-    input.lines().map(part1::parse_hand).collect()
+/// Parses hands that are valid under `rules`.
+pub fn parse_with(rules: &RuleSet, input: &str) -> Result<Vec<RawHand>, ParseError> {
+    lines(DAY, input)
+        .map(|line| parse_hand(rules, line))
+        .collect()
 }
 
-#[aoc_generator(day7, part2)]
-fn input_generator_part2(input: &str) -> Vec<Hand> {
-    input.lines().map(part2::parse_hand).collect()
+/// Reads one hand and its bid per line.
+#[aoc_generator(day7)]
+pub fn parse(input: &str) -> Result<Vec<RawHand>, ParseError> {
+    parse_with(&RuleSet::standard(), input)
 }
 
-#[aoc(day7, part1)]
-fn part1(hands: &[Hand]) -> u32 {
-    let mut hands = hands.to_owned();
+pub fn total_winnings(mut hands: Vec<Hand>) -> u32 {
     hands.sort();
     hands
         .iter()
@@ -232,9 +286,163 @@ fn part1(hands: &[Hand]) -> u32 {
         .sum()
 }
 
+/// Why a hand ranked where it did.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HandReport {
+    pub cards: String,
+    pub hand_type: String,
+    /// The cards with each wildcard replaced by the card it stands in for.
+    pub substituted: String,
+    pub rank: usize,
+    pub bid: u32,
+    pub winnings: u32,
+    /// Where the hand's cards first beat those of the hand ranked just below, if both have the
+    /// same type.
+    pub tie_break: Option<usize>,
+}
+
+impl Display for HandReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{:>5} {} {:<16} {} {:>5} {:>10}",
+            self.rank, self.cards, self.hand_type, self.substituted, self.bid, self.winnings
+        )?;
+        if let Some(i) = self.tie_break {
+            write!(f, "  beats rank {} at card {}", self.rank - 1, i + 1)?;
+        }
+        Ok(())
+    }
+}
+
+/// Ranks the hands under `rules` and explains each one, weakest first.
+pub fn report(rules: &RuleSet, hands: &[RawHand]) -> Vec<HandReport> {
+    let mut hands = hands
+        .iter()
+        .map(|raw| (rules.hand(raw), raw))
+        .collect::<Vec<_>>();
+    hands.sort_by(|(a, _), (b, _)| a.cmp(b));
+    let show = |cards: &[Card]| -> String {
+        cards
+            .iter()
+            .map(|&card| rules.cards[card as usize])
+            .collect()
+    };
+    hands
+        .iter()
+        .enumerate()
+        .map(|(i, (hand, raw))| {
+            let (_, substituted) = rules.substitute(&hand.cards);
+            let tie_break = i
+                .checked_sub(1)
+                .map(|j| &hands[j].0)
+                .filter(|below| below.hand_type == hand.hand_type)
+                .and_then(|below| tie_break(&below.cards, &hand.cards));
+            HandReport {
+                cards: raw.cards.clone(),
+                hand_type: rules.hand_types[hand.hand_type].name.clone(),
+                substituted: show(&substituted),
+                rank: i + 1,
+                bid: hand.bid,
+                winnings: (i + 1) as u32 * hand.bid,
+                tie_break,
+            }
+        })
+        .collect()
+}
+
+impl RuleSet {
+    /// Classifies hands, remembering the type of each combination of cards seen so far.
+    fn classifier(&self) -> impl FnMut(&[Card]) -> usize + '_ {
+        let mut types = HashMap::new();
+        move |cards| {
+            let mut sorted = cards.to_vec();
+            sorted.sort_unstable();
+            *types.entry(sorted).or_insert_with(|| self.classify(cards))
+        }
+    }
+
+    /// Every hand of `hand_size` cards, in order of their cards.
+    pub fn all_hands(&self) -> impl Iterator<Item = Vec<Card>> + '_ {
+        let n = self.cards.len() as u64;
+        (0..n.pow(self.hand_size as u32)).map(move |mut i| {
+            let mut cards = vec![0; self.hand_size];
+            for card in cards.iter_mut().rev() {
+                *card = (i % n) as Card;
+                i /= n;
+            }
+            cards
+        })
+    }
+
+    /// How many of all the hands have each hand type.
+    pub fn type_distribution(&self) -> Vec<u64> {
+        let mut classify = self.classifier();
+        let mut counts = vec![0; self.hand_types.len()];
+        for cards in self.all_hands() {
+            counts[classify(&cards)] += 1;
+        }
+        counts
+    }
+
+    /// The probability that the cards beat a hand drawn uniformly from all the hands.
+    pub fn win_probability(&self, cards: &[Card]) -> f64 {
+        let mut classify = self.classifier();
+        let hand_type = classify(cards);
+        let (mut beaten, mut total) = (0u64, 0u64);
+        for other in self.all_hands() {
+            let ordering = hand_type
+                .cmp(&classify(&other))
+                .then_with(|| compare_cards(cards, &other));
+            beaten += (ordering == Ordering::Greater) as u64;
+            total += 1;
+        }
+        beaten as f64 / total as f64
+    }
+
+    /// Reads a hand where `?` stands for an unknown card.
+    pub fn partial_hand(&self, cards: &str) -> Option<Vec<Option<Card>>> {
+        cards
+            .chars()
+            .map(|c| match c {
+                '?' => Some(None),
+                c => self.card(c).map(Some),
+            })
+            .collect()
+    }
+
+    /// The strongest hand the unknown cards could make, with its hand type.
+    pub fn strongest(&self, partial: &[Option<Card>]) -> (usize, Vec<Card>) {
+        let unknown = partial.iter().filter(|card| card.is_none()).count();
+        let n = self.cards.len() as u64;
+        let mut classify = self.classifier();
+        (0..n.pow(unknown as u32))
+            .map(|mut i| {
+                let mut cards = partial.to_vec();
+                for card in cards.iter_mut().rev().filter(|card| card.is_none()) {
+                    *card = Some((i % n) as Card);
+                    i /= n;
+                }
+                let cards = cards.into_iter().flatten().collect::<Vec<_>>();
+                (classify(&cards), cards)
+            })
+            .max_by(|(t1, c1), (t2, c2)| t1.cmp(t2).then_with(|| compare_cards(c1, c2)))
+            .unwrap()
+    }
+}
+
+/// The total winnings with standard rules.
+#[aoc(day7, part1)]
+pub fn part1(hands: &[RawHand]) -> u32 {
+    let rules = RuleSet::standard();
+    total_winnings(hands.iter().map(|hand| rules.hand(hand)).collect())
+}
+
+/// The total winnings with `J` cards as jokers.
 #[aoc(day7, part2)]
-fn part2(hands: &[Hand]) -> u32 {
-    part1(hands)
+pub fn part2(hands: &[RawHand]) -> u32 {
+    let rules = RuleSet::jokers();
+    total_winnings(hands.iter().map(|hand| rules.hand(hand)).collect())
 }
 
 #[cfg(test)]
@@ -249,11 +457,128 @@ QQQJA 483";
 
     #[test]
     fn sample1() {
-        assert_eq!(part1(&input_generator(INPUT)), 6440);
+        assert_eq!(part1(&parse(INPUT).unwrap()), 6440);
     }
 
     #[test]
     fn sample2() {
-        assert_eq!(part2(&input_generator_part2(INPUT)), 5905);
+        assert_eq!(part2(&parse(INPUT).unwrap()), 5905);
+    }
+
+    #[test]
+    fn explain_ranking() {
+        let report = report(&RuleSet::jokers(), &parse(INPUT).unwrap());
+        let rows = report.iter().map(|row| row.to_string()).collect::<Vec<_>>();
+        assert_eq!(
+            rows,
+            vec![
+                "    1 32T3K one pair         32T3K   765        765",
+                "    2 KK677 two pair         KK677    28         56",
+                "    3 T55J5 four of a kind   T5555   684       2052",
+                "    4 QQQJA four of a kind   QQQQA   483       1932  beats rank 3 at card 1",
+                "    5 KTJJT four of a kind   KTTTT   220       1100  beats rank 4 at card 1",
+            ]
+        );
+        assert_eq!(report.iter().map(|row| row.winnings).sum::<u32>(), 5905);
+        let (_, substituted) = RuleSet::jokers().substitute(&[0; 5]);
+        assert_eq!(substituted, vec![12; 5]);
+    }
+
+    #[test]
+    fn enumerate_hands() {
+        let standard = RuleSet::standard();
+        assert_eq!(
+            standard.type_distribution(),
+            vec![154440, 171600, 25740, 17160, 1560, 780, 13]
+        );
+        let jokers = RuleSet::jokers().type_distribution();
+        assert_eq!(jokers.iter().sum::<u64>(), 13u64.pow(5));
+        // No jokers and no pairs, or at most one kind of card besides the jokers.
+        assert_eq!(jokers[0], 12 * 11 * 10 * 9 * 8);
+        assert_eq!(jokers[6], 1 + 12 * 31);
+
+        let cards = |rules: &RuleSet, s: &str| {
+            s.chars()
+                .map(|c| rules.card(c).unwrap())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(standard.win_probability(&cards(&standard, "23456")), 0.0);
+        assert_eq!(
+            standard.win_probability(&cards(&standard, "AAAAA")),
+            (13f64.powi(5) - 1.0) / 13f64.powi(5)
+        );
+        assert_eq!(
+            standard.win_probability(&cards(&standard, "22222")),
+            (13f64.powi(5) - 13.0) / 13f64.powi(5)
+        );
+
+        let strongest = |rules: &RuleSet, s: &str| {
+            let (hand_type, cards) = rules.strongest(&rules.partial_hand(s).unwrap());
+            let cards = cards
+                .iter()
+                .map(|&card| rules.cards[card as usize])
+                .collect::<String>();
+            (rules.hand_types[hand_type].name.clone(), cards)
+        };
+        assert_eq!(
+            strongest(&standard, "?????"),
+            ("five of a kind".into(), "AAAAA".into())
+        );
+        assert_eq!(
+            strongest(&standard, "2?3??"),
+            ("four of a kind".into(), "23333".into())
+        );
+        assert_eq!(
+            strongest(&RuleSet::jokers(), "J?J??"),
+            ("five of a kind".into(), "JAJAA".into())
+        );
+        assert_eq!(standard.partial_hand("2?X"), None);
+    }
+
+    #[test]
+    fn custom_rules() {
+        let mut rules = RuleSet::jokers();
+        rules.cards.insert(0, '*');
+        rules.wildcards.push('*');
+        rules.hand_size = 6;
+        rules.hand_types.push(HandType::new("two triples", &[3, 3]));
+        rules.hand_types.push(HandType::new("six of a kind", &[6]));
+        rules.hand_types.swap(6, 7);
+        let hands = parse_with(&rules, "22233J 1\n*22233 2\n2223J4 3\n2345** 4").unwrap();
+        let hands = hands
+            .iter()
+            .map(|hand| rules.hand(hand))
+            .collect::<Vec<_>>();
+        let types = hands
+            .iter()
+            .map(|hand| rules.hand_types[hand.hand_type].name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            types,
+            vec![
+                "two triples",
+                "two triples",
+                "four of a kind",
+                "three of a kind"
+            ]
+        );
+        assert!(hands[1] < hands[0]);
+        assert_eq!(total_winnings(hands), 20);
+        let err = parse_with(&rules, "22233J 1\n22233 2").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "day7 line 2 col 1: expected 6 cards, found 5"
+        );
+    }
+
+    #[test]
+    fn unknown_card() {
+        let err = parse("32T3K 765\nT55X5 684").unwrap_err();
+        assert_eq!(err.to_string(), "day7 line 2 col 4: unknown card 'X'");
+        let err = parse("32T3 765").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "day7 line 1 col 1: expected 5 cards, found 4"
+        );
     }
 }
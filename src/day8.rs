@@ -3,179 +3,490 @@ use aoc_runner_derive::aoc_generator;
 
 use num::integer::lcm;
 use std::collections::HashMap;
+use std::collections::HashSet;
+use std::fmt::Write;
 
-#[derive(Debug)]
-enum Instruction {
+use crate::parse::{lines, Line, ParseError};
+
+const DAY: u32 = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Instruction {
     Left,
     Right,
 }
 
-fn parse_instruction(c: char) -> Instruction {
+/// A node of a network, numbered in the order the nodes are listed.
+pub type NodeId = u32;
+
+/// A set of nodes, one bit per node.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NodeSet {
+    words: Vec<u64>,
+}
+
+impl NodeSet {
+    pub fn new(len: usize) -> NodeSet {
+        NodeSet {
+            words: vec![0; len.div_ceil(64)],
+        }
+    }
+
+    pub fn insert(&mut self, node: NodeId) {
+        self.words[node as usize / 64] |= 1 << (node % 64);
+    }
+
+    pub fn contains(&self, node: NodeId) -> bool {
+        self.words[node as usize / 64] & (1 << (node % 64)) != 0
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = NodeId> + '_ {
+        self.words.iter().enumerate().flat_map(|(i, &word)| {
+            (0..64)
+                .filter(move |bit| word & (1 << bit) != 0)
+                .map(move |bit| (i * 64 + bit) as NodeId)
+        })
+    }
+}
+
+/// Picks out nodes by name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NodePattern {
+    Exact(String),
+    Suffix(String),
+    /// A glob, where `?` matches any one character and `*` any run of them.
+    Pattern(String),
+    Set(HashSet<String>),
+}
+
+impl NodePattern {
+    pub fn matches(&self, name: &str) -> bool {
+        match self {
+            NodePattern::Exact(exact) => name == exact,
+            NodePattern::Suffix(suffix) => name.ends_with(suffix.as_str()),
+            NodePattern::Pattern(pattern) => glob(
+                &pattern.chars().collect::<Vec<_>>(),
+                &name.chars().collect::<Vec<_>>(),
+            ),
+            NodePattern::Set(names) => names.contains(name),
+        }
+    }
+}
+
+fn glob(pattern: &[char], name: &[char]) -> bool {
+    match pattern.split_first() {
+        None => name.is_empty(),
+        Some(('*', rest)) => (0..=name.len()).any(|i| glob(rest, &name[i..])),
+        Some((&p, rest)) => match name.split_first() {
+            Some((&c, name)) => (p == '?' || p == c) && glob(rest, name),
+            None => false,
+        },
+    }
+}
+
+/// The left/right instructions and the network of nodes they are followed through, with the
+/// nodes interned so that walking never has to look at their names.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Network {
+    pub instructions: Vec<Instruction>,
+    names: Vec<String>,
+    ids: HashMap<String, NodeId>,
+    left: Vec<NodeId>,
+    right: Vec<NodeId>,
+    /// The nodes ending in `A`.
+    pub starts: NodeSet,
+    /// The nodes ending in `Z`.
+    pub goals: NodeSet,
+}
+
+impl Network {
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    pub fn id(&self, name: &str) -> Option<NodeId> {
+        self.ids.get(name).copied()
+    }
+
+    pub fn name(&self, node: NodeId) -> &str {
+        &self.names[node as usize]
+    }
+
+    pub fn left(&self, node: NodeId) -> NodeId {
+        self.left[node as usize]
+    }
+
+    pub fn right(&self, node: NodeId) -> NodeId {
+        self.right[node as usize]
+    }
+
+    pub fn next(&self, node: NodeId, instr: Instruction) -> NodeId {
+        match instr {
+            Instruction::Left => self.left(node),
+            Instruction::Right => self.right(node),
+        }
+    }
+
+    pub fn select(&self, pattern: &NodePattern) -> NodeSet {
+        let mut nodes = NodeSet::new(self.len());
+        for (id, name) in self.names.iter().enumerate() {
+            if pattern.matches(name) {
+                nodes.insert(id as NodeId);
+            }
+        }
+        nodes
+    }
+
+    /// The nodes a walker from `start` passes through until it first stands on a goal, or `None`
+    /// if it never does. A walker starting on a goal is there after no steps.
+    pub fn path(&self, start: NodeId, goal: &NodePattern) -> Option<Vec<NodeId>> {
+        let goals = self.select(goal);
+        let instrs = &self.instructions;
+        let mut visited = vec![false; instrs.len() * self.len()];
+        let mut path = vec![start];
+        let mut node = start;
+        for step in 0.. {
+            if goals.contains(node) {
+                return Some(path);
+            }
+            let stepmod = step % instrs.len();
+            let state = stepmod * self.len() + node as usize;
+            if visited[state] {
+                return None;
+            }
+            visited[state] = true;
+            node = self.next(node, instrs[stepmod]);
+            path.push(node);
+        }
+        unreachable!()
+    }
+
+    /// How many steps a walker from `start` takes to first stand on a goal.
+    pub fn steps(&self, start: NodeId, goal: &NodePattern) -> Option<usize> {
+        self.path(start, goal).map(|path| path.len() - 1)
+    }
+
+    /// The first step at which walkers from every start stand on goals at once.
+    pub fn synchronized(&self, start: &NodePattern, goal: &NodePattern) -> Option<usize> {
+        let goals = self.select(goal);
+        let cycles = self
+            .select(start)
+            .iter()
+            .map(|start| ghost_cycle(self, start, &goals))
+            .collect::<Vec<_>>();
+        align(&cycles)
+    }
+}
+
+fn parse_instruction(c: char) -> Option<Instruction> {
     match c {
-        'L' => Instruction::Left,
-        'R' => Instruction::Right,
-        _ => panic!(),
+        'L' => Some(Instruction::Left),
+        'R' => Some(Instruction::Right),
+        _ => None,
     }
 }
 
-fn parse_node(line: &str) -> (String, (String, String)) {
-    let (name, rest) = line.split_once(" = ").unwrap();
-    let rest = rest.trim_matches(|c| c == '(' || c == ')');
-    let (left, right) = rest.split_once(", ").unwrap();
-    (name.to_string(), (left.to_string(), right.to_string()))
+fn parse_node<'a>(line: Line<'a>) -> Result<(&'a str, (&'a str, &'a str)), ParseError> {
+    let (name, rest) = line.split_once(line.text, " = ")?;
+    let rest = rest
+        .strip_prefix('(')
+        .and_then(|rest| rest.strip_suffix(')'))
+        .ok_or_else(|| line.error_at(rest, "expected '(left, right)'"))?;
+    let (left, right) = line.split_once(rest, ", ")?;
+    Ok((name, (left, right)))
 }
 
+/// Reads the instructions and resolves the nodes of the network.
 #[aoc_generator(day8)]
-fn input_generator(input: &str) -> (Vec<Instruction>, HashMap<String, (String, String)>) {
-    let mut lines = input.lines();
-    let instrs = lines
-        .next()
-        .unwrap()
+pub fn parse(input: &str) -> Result<Network, ParseError> {
+    let mut lines = lines(DAY, input);
+    let line = lines.expect("instructions")?;
+    let instructions = line
+        .text
         .chars()
-        .map(parse_instruction)
-        .collect();
-    lines.next();
-    let map = lines.map(parse_node).collect();
-    (instrs, map)
+        .enumerate()
+        .map(|(x, c)| parse_instruction(c).ok_or_else(|| line.unknown_char(x, c, "instruction")))
+        .collect::<Result<Vec<_>, _>>()?;
+    if instructions.is_empty() {
+        return Err(line.error("expected instructions"));
+    }
+    lines.expect_blank()?;
+    let nodes = lines
+        .map(|line| Ok((line, parse_node(line)?)))
+        .collect::<Result<Vec<_>, ParseError>>()?;
+    let mut ids = HashMap::new();
+    for (id, (line, (name, _))) in nodes.iter().enumerate() {
+        if ids.insert(name.to_string(), id as NodeId).is_some() {
+            return Err(line.error_at(name, format!("duplicate node '{}'", name)));
+        }
+    }
+    let resolve = |line: &Line, name: &str| {
+        ids.get(name)
+            .copied()
+            .ok_or_else(|| line.error_at(name, format!("unknown node '{}'", name)))
+    };
+    let (mut left, mut right) = (vec![], vec![]);
+    let (mut starts, mut goals) = (NodeSet::new(nodes.len()), NodeSet::new(nodes.len()));
+    for (id, (line, (name, (l, r)))) in nodes.iter().enumerate() {
+        left.push(resolve(line, l)?);
+        right.push(resolve(line, r)?);
+        if name.ends_with('A') {
+            starts.insert(id as NodeId);
+        }
+        if name.ends_with('Z') {
+            goals.insert(id as NodeId);
+        }
+    }
+    Ok(Network {
+        instructions,
+        names: nodes
+            .iter()
+            .map(|(_, (name, _))| name.to_string())
+            .collect(),
+        ids,
+        left,
+        right,
+        starts,
+        goals,
+    })
 }
 
+/// The number of steps from `AAA` to `ZZZ`.
 #[aoc(day8, part1)]
-fn part1(input: &(Vec<Instruction>, HashMap<String, (String, String)>)) -> usize {
-    let (instrs, map) = input;
-    let mut node = "AAA";
-    instrs
-        .iter()
-        .cycle()
-        .enumerate()
-        .find(|(_, instr)| {
-            node = match instr {
-                Instruction::Left => &map.get(node).unwrap().0,
-                Instruction::Right => &map.get(node).unwrap().1,
-            };
-            node == "ZZZ"
-        })
+pub fn part1(network: &Network) -> usize {
+    let start = network.id("AAA").unwrap();
+    network
+        .steps(start, &NodePattern::Exact("ZZZ".to_string()))
         .unwrap()
-        .0
-        + 1
 }
 
+/// Assumes that each ghost first reaches a goal after exactly one loop, and never otherwise.
+/// `None` if some ghost never reaches a goal at all.
 #[aoc(day8, part2, simplified)]
-fn part2_simplified(input: &(Vec<Instruction>, HashMap<String, (String, String)>)) -> usize {
-    let (instrs, map) = input;
-    let mut ghosts: HashMap<usize, &str> = map
-        .keys()
-        .filter(|k| k.ends_with('A'))
-        .map(|k| k.as_str())
-        .enumerate()
-        .collect();
-    let ghost_count = ghosts.len();
-    let mut goals: Vec<Vec<usize>> = vec![vec![]; ghost_count];
-    let mut visited: Vec<HashMap<(usize, &str), usize>> = vec![HashMap::new(); ghost_count];
-    let mut loops: HashMap<usize, (usize, usize)> = HashMap::new();
-    instrs.iter().cycle().enumerate().find(|&(step, instr)| {
-        ghosts = ghosts
+pub fn part2_simplified(network: &Network) -> Option<usize> {
+    ghost_cycles(network)
+        .iter()
+        .map(|cycle| cycle.goals.first().copied())
+        .try_fold(1, |steps, goal| Some(lcm(steps, goal?)))
+}
+
+/// Where a ghost's walk ends up looping: after `prefix` steps it repeats every `length` steps.
+/// `goals` are the steps within the first `prefix + length` that end on a goal node.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GhostCycle {
+    pub prefix: usize,
+    pub length: usize,
+    pub goals: Vec<usize>,
+}
+
+impl GhostCycle {
+    pub fn is_goal(&self, step: usize) -> bool {
+        let step = match step.checked_sub(self.prefix) {
+            Some(looped) => self.prefix + looped % self.length,
+            None => step,
+        };
+        self.goals.contains(&step)
+    }
+
+    /// The goal steps inside the loop, which recur every `length` steps.
+    fn looped_goals(&self) -> impl Iterator<Item = usize> + '_ {
+        self.goals
             .iter()
-            .filter_map(|(&index, &node)| {
-                let stepmod = step % instrs.len();
-                if node.ends_with('Z') {
-                    goals[index].push(step);
-                }
-                visited[index].insert((stepmod, node), step);
-                let new = match instr {
-                    Instruction::Left => map.get(node).unwrap().0.as_str(),
-                    Instruction::Right => map.get(node).unwrap().1.as_str(),
-                };
-                if visited[index].contains_key(&(stepmod + 1, new)) {
-                    loops.insert(
-                        index,
-                        (
-                            *visited[index].get(&(stepmod + 1, new)).unwrap(),
-                            step - stepmod,
-                        ),
-                    );
-                    None
-                } else {
-                    Some((index, new))
-                }
-            })
-            .collect();
-        ghosts.is_empty()
-    });
-    goals.iter().map(|gs| *gs.first().unwrap()).fold(1, lcm)
-}
-
-//#[aoc(day8, part2, generic)]
-fn part2_generic(input: &(Vec<Instruction>, HashMap<String, (String, String)>)) -> usize {
-    let (instrs, map) = input;
-    let mut ghosts: HashMap<usize, &str> = map
-        .keys()
-        .filter(|k| k.ends_with('A'))
-        .map(|k| k.as_str())
-        .enumerate()
-        .collect();
-    let ghost_count = ghosts.len();
-    let mut goals: Vec<Vec<usize>> = vec![vec![]; ghost_count];
-    let mut visited: Vec<HashMap<(usize, &str), usize>> = vec![HashMap::new(); ghost_count];
-    let mut loops: HashMap<usize, (usize, usize)> = HashMap::new();
-    instrs.iter().cycle().enumerate().find(|&(step, instr)| {
-        ghosts = ghosts
+            .copied()
+            .filter(|&goal| goal >= self.prefix)
+    }
+}
+
+/// Follows a ghost from `start` until it is back in a state it has been in: the same node at the
+/// same point of the instructions.
+pub fn ghost_cycle(network: &Network, start: NodeId, goals: &NodeSet) -> GhostCycle {
+    let instrs = &network.instructions;
+    let mut visited = vec![None; instrs.len() * network.len()];
+    let mut goal_steps = vec![];
+    let mut node = start;
+    for step in 0.. {
+        let stepmod = step % instrs.len();
+        let state = stepmod * network.len() + node as usize;
+        if let Some(prefix) = visited[state] {
+            return GhostCycle {
+                prefix,
+                length: step - prefix,
+                goals: goal_steps,
+            };
+        }
+        visited[state] = Some(step);
+        if goals.contains(node) {
+            goal_steps.push(step);
+        }
+        node = network.next(node, instrs[stepmod]);
+    }
+    unreachable!()
+}
+
+/// The solutions to both `x = r1 (mod m1)` and `x = r2 (mod m2)`, if there are any.
+fn crt((r1, m1): (i128, i128), (r2, m2): (i128, i128)) -> Option<(i128, i128)> {
+    let (g, p, _) = extended_gcd(m1, m2);
+    if (r2 - r1) % g != 0 {
+        return None;
+    }
+    let m = m1 / g * m2;
+    let k = ((r2 - r1) / g * p).rem_euclid(m2 / g);
+    Some(((r1 + m1 * k).rem_euclid(m), m))
+}
+
+/// Returns `(g, p, q)` with `g = gcd(a, b) = p * a + q * b`.
+fn extended_gcd(a: i128, b: i128) -> (i128, i128, i128) {
+    if b == 0 {
+        (a, 1, 0)
+    } else {
+        let (g, p, q) = extended_gcd(b, a % b);
+        (g, q, p - a / b * q)
+    }
+}
+
+/// The first step at which every ghost is on a goal at once, if there is one.
+pub fn align(cycles: &[GhostCycle]) -> Option<usize> {
+    let latest = cycles.iter().max_by_key(|cycle| cycle.prefix)?;
+    // Before the last ghost starts looping, only its goals from before the loop can be shared.
+    let early = latest
+        .goals
+        .iter()
+        .copied()
+        .filter(|&goal| goal < latest.prefix)
+        .find(|&goal| cycles.iter().all(|cycle| cycle.is_goal(goal)));
+    if early.is_some() {
+        return early;
+    }
+    // After that, each ghost is on a goal exactly at the steps congruent to its looped goals.
+    let mut residues = vec![(0, 1)];
+    for cycle in cycles {
+        let mut combined = residues
             .iter()
-            .filter_map(|(&index, &node)| {
-                let stepmod = step % instrs.len();
-                if node.ends_with('Z') {
-                    goals[index].push(step);
-                }
-                visited[index].insert((stepmod, node), step);
-                let new = match instr {
-                    Instruction::Left => map.get(node).unwrap().0.as_str(),
-                    Instruction::Right => map.get(node).unwrap().1.as_str(),
-                };
-                if visited[index].contains_key(&(stepmod + 1, new)) {
-                    loops.insert(
-                        index,
-                        (
-                            *visited[index].get(&(stepmod + 1, new)).unwrap(),
-                            step - stepmod,
-                        ),
-                    );
-                    None
-                } else {
-                    Some((index, new))
-                }
+            .flat_map(|&residue| {
+                cycle
+                    .looped_goals()
+                    .filter_map(move |goal| crt(residue, (goal as i128, cycle.length as i128)))
             })
-            .collect();
-        ghosts.is_empty()
-    });
-    let mut goal_steps = (0..ghost_count)
-        .map(|index| {
-            let (_loop_start, loop_length) = loops[&index];
-            let num_goals = goals[index].len();
-            goals[index]
-                .iter()
-                .cycle()
-                .enumerate()
-                .map(move |(i, goal)| goal + (i / num_goals) * loop_length)
-        })
-        .collect::<Vec<_>>();
-    let mut curs = vec![];
-    for goals in &mut goal_steps {
-        curs.push(goals.next().unwrap());
-    }
-    loop {
-        let (max_i, &max) = curs.iter().enumerate().max_by_key(|(_, &cur)| cur).unwrap();
-        if curs.iter().all(|&cur| cur == max) {
-            return max;
-        } else {
-            for (i, goals) in goal_steps.iter_mut().enumerate() {
-                if i == max_i {
-                    continue;
-                }
-                while curs[i] < max {
-                    curs[i] = goals.next().unwrap();
-                }
+            .collect::<Vec<_>>();
+        combined.sort();
+        combined.dedup();
+        residues = combined;
+    }
+    let start = latest.prefix as i128;
+    residues
+        .into_iter()
+        .map(|(r, m)| start + (r - start).rem_euclid(m))
+        .min()
+        .and_then(|step| usize::try_from(step).ok())
+}
+
+pub fn ghost_cycles(network: &Network) -> Vec<GhostCycle> {
+    network
+        .starts
+        .iter()
+        .map(|start| ghost_cycle(network, start, &network.goals))
+        .collect()
+}
+
+/// Solves part 2 without assuming anything about where the ghosts' loops and goals lie.
+#[aoc(day8, part2, generic)]
+pub fn part2_generic(network: &Network) -> Option<usize> {
+    network.synchronized(
+        &NodePattern::Suffix("A".to_string()),
+        &NodePattern::Suffix("Z".to_string()),
+    )
+}
+
+/// The edges a ghost from `start` follows around its loop, as the node it leaves by each one
+/// and the instruction it takes there.
+pub fn loop_edges(
+    network: &Network,
+    start: NodeId,
+    cycle: &GhostCycle,
+) -> HashSet<(NodeId, Instruction)> {
+    let instrs = &network.instructions;
+    let mut edges = HashSet::new();
+    let mut node = start;
+    for step in 0..cycle.prefix + cycle.length {
+        let instr = instrs[step % instrs.len()];
+        if step >= cycle.prefix {
+            edges.insert((node, instr));
+        }
+        node = network.next(node, instr);
+    }
+    edges
+}
+
+/// A Graphviz quoted identifier for `name`.
+fn quote(name: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in name.chars() {
+        if matches!(c, '"' | '\\') {
+            quoted.push('\\');
+        }
+        quoted.push(c);
+    }
+    quoted.push('"');
+    quoted
+}
+
+const LOOP_COLOURS: [&str; 6] = ["red", "blue", "darkgreen", "orange", "purple", "brown"];
+
+/// Renders the network as a Graphviz graph, with starts and goals filled in and the loop each
+/// ghost ends up in drawn in its own colour.
+pub fn to_dot(network: &Network) -> String {
+    let mut colours: HashMap<(NodeId, Instruction), Vec<&str>> = HashMap::new();
+    for (i, start) in network.starts.iter().enumerate() {
+        let cycle = ghost_cycle(network, start, &network.goals);
+        for edge in loop_edges(network, start, &cycle) {
+            colours
+                .entry(edge)
+                .or_default()
+                .push(LOOP_COLOURS[i % LOOP_COLOURS.len()]);
+        }
+    }
+    let mut dot = String::from("digraph day8 {\n");
+    for node in 0..network.len() as NodeId {
+        let name = network.name(node);
+        if network.starts.contains(node) {
+            writeln!(
+                dot,
+                "    {} [style=filled, fillcolor=palegreen];",
+                quote(name)
+            )
+            .unwrap();
+        } else if network.goals.contains(node) {
+            writeln!(
+                dot,
+                "    {} [style=filled, fillcolor=lightpink];",
+                quote(name)
+            )
+            .unwrap();
+        }
+    }
+    for node in 0..network.len() as NodeId {
+        for (instr, label) in [(Instruction::Left, 'L'), (Instruction::Right, 'R')] {
+            let target = network.next(node, instr);
+            write!(
+                dot,
+                "    {} -> {} [label=\"{}\"",
+                quote(network.name(node)),
+                quote(network.name(target)),
+                label
+            )
+            .unwrap();
+            if let Some(colours) = colours.get(&(node, instr)) {
+                write!(dot, ", color=\"{}\", penwidth=2", colours.join(":")).unwrap();
             }
+            dot.push_str("];\n");
         }
     }
+    dot.push_str("}\n");
+    dot
 }
 
 #[cfg(test)]
@@ -193,7 +504,7 @@ DDD = (DDD, DDD)
 EEE = (EEE, EEE)
 GGG = (GGG, GGG)
 ZZZ = (ZZZ, ZZZ)";
-        assert_eq!(part1(&input_generator(input)), 2);
+        assert_eq!(part1(&parse(input).unwrap()), 2);
     }
 
     #[test]
@@ -203,7 +514,7 @@ ZZZ = (ZZZ, ZZZ)";
 AAA = (BBB, BBB)
 BBB = (AAA, ZZZ)
 ZZZ = (ZZZ, ZZZ)";
-        assert_eq!(part1(&input_generator(input)), 6);
+        assert_eq!(part1(&parse(input).unwrap()), 6);
     }
 
     #[test]
@@ -218,8 +529,8 @@ ZZZ = (ZZZ, ZZZ)";
 22C = (22Z, 22Z)
 22Z = (22B, 22B)
 XXX = (XXX, XXX)";
-        assert_eq!(part2_simplified(&input_generator(input)), 6);
-        assert_eq!(part2_generic(&input_generator(input)), 6);
+        assert_eq!(part2_simplified(&parse(input).unwrap()), Some(6));
+        assert_eq!(part2_generic(&parse(input).unwrap()), Some(6));
     }
 
     #[test]
@@ -234,6 +545,165 @@ XXX = (XXX, XXX)";
 22C = (22B, 22B)
 22Z = (22C, 22C)
 XXX = (XXX, XXX)";
-        assert_eq!(part2_generic(&input_generator(input)), 5);
+        let network = parse(input).unwrap();
+        assert_eq!(
+            ghost_cycles(&network),
+            vec![
+                GhostCycle {
+                    prefix: 1,
+                    length: 2,
+                    goals: vec![1]
+                },
+                GhostCycle {
+                    prefix: 1,
+                    length: 6,
+                    goals: vec![2, 5]
+                },
+            ]
+        );
+        assert_eq!(part2_generic(&network), Some(5));
+    }
+
+    #[test]
+    fn alignment() {
+        // The first ghost only reaches a goal before it starts looping.
+        let input = "L
+
+11A = (11Z, 11Z)
+11Z = (XXX, XXX)
+22A = (22Z, 22Z)
+22Z = (22Z, 22Z)
+XXX = (XXX, XXX)";
+        assert_eq!(part2_generic(&parse(input).unwrap()), Some(1));
+        // One ghost is on a goal after odd numbers of steps, the other after even ones.
+        let input = "LR
+
+11A = (11Z, 11Z)
+11Z = (11A, 11A)
+22A = (22B, 22B)
+22B = (22Z, 22Z)
+22Z = (22B, 22B)";
+        assert_eq!(part2_generic(&parse(input).unwrap()), None);
+        // The second ghost never reaches a goal.
+        let input = "L
+
+11A = (11Z, 11Z)
+11Z = (11Z, 11Z)
+22A = (22A, 22A)";
+        assert_eq!(part2_simplified(&parse(input).unwrap()), None);
+        assert_eq!(part2_generic(&parse(input).unwrap()), None);
+        let cycle = |prefix, length, goals| GhostCycle {
+            prefix,
+            length,
+            goals,
+        };
+        assert_eq!(
+            align(&[cycle(0, 4, vec![3]), cycle(2, 6, vec![2, 7])]),
+            Some(7)
+        );
+        assert_eq!(
+            align(&[cycle(0, 15, vec![6]), cycle(0, 21, vec![15])]),
+            Some(36)
+        );
+    }
+
+    #[test]
+    fn queries() {
+        let input = "RL
+
+AAA = (BBB, CCC)
+BBB = (DDD, EEE)
+CCC = (ZZZ, GGG)
+DDD = (DDD, DDD)
+EEE = (EEE, EEE)
+GGG = (GGG, GGG)
+ZZZ = (ZZZ, ZZZ)";
+        let network = parse(input).unwrap();
+        let exact = |name: &str| NodePattern::Exact(name.to_string());
+        let set = |names: &[&str]| NodePattern::Set(names.iter().map(|s| s.to_string()).collect());
+        let names = |path: Vec<NodeId>| {
+            path.into_iter()
+                .map(|node| network.name(node))
+                .collect::<Vec<_>>()
+        };
+        let aaa = network.id("AAA").unwrap();
+        assert_eq!(
+            network.path(aaa, &exact("ZZZ")).map(names),
+            Some(vec!["AAA", "CCC", "ZZZ"])
+        );
+        assert_eq!(network.steps(aaa, &exact("AAA")), Some(0));
+        assert_eq!(network.steps(aaa, &exact("GGG")), None);
+        assert_eq!(network.steps(aaa, &set(&["GGG", "ZZZ"])), Some(2));
+        let pattern = |glob: &str| NodePattern::Pattern(glob.to_string());
+        assert!(pattern("?C*").matches("CCC"));
+        assert!(pattern("*").matches(""));
+        assert!(!pattern("?C?").matches("CC"));
+        assert_eq!(network.select(&pattern("???")).iter().count(), 7);
+        // From BBB and CCC the walkers move on to different nodes that lead nowhere else.
+        assert_eq!(
+            network.synchronized(&set(&["BBB", "CCC"]), &set(&["EEE", "GGG"])),
+            Some(1)
+        );
+        assert_eq!(
+            network.synchronized(&set(&["BBB", "CCC"]), &exact("EEE")),
+            None
+        );
+    }
+
+    #[test]
+    fn dot_export() {
+        let input = "LR
+
+11A = (11Z, XXX)
+11Z = (XXX, 11A)
+22A = (22Z, 22Z)
+22Z = (22A, 11A)
+XXX = (XXX, XXX)";
+        // The second ghost joins the first one's loop, leaving its own nodes behind.
+        let dot = to_dot(&parse(input).unwrap());
+        let lines = dot.lines().collect::<Vec<_>>();
+        assert_eq!(
+            lines,
+            vec![
+                "digraph day8 {",
+                "    \"11A\" [style=filled, fillcolor=palegreen];",
+                "    \"11Z\" [style=filled, fillcolor=lightpink];",
+                "    \"22A\" [style=filled, fillcolor=palegreen];",
+                "    \"22Z\" [style=filled, fillcolor=lightpink];",
+                "    \"11A\" -> \"11Z\" [label=\"L\", color=\"red:blue\", penwidth=2];",
+                "    \"11A\" -> \"XXX\" [label=\"R\"];",
+                "    \"11Z\" -> \"XXX\" [label=\"L\"];",
+                "    \"11Z\" -> \"11A\" [label=\"R\", color=\"red:blue\", penwidth=2];",
+                "    \"22A\" -> \"22Z\" [label=\"L\"];",
+                "    \"22A\" -> \"22Z\" [label=\"R\"];",
+                "    \"22Z\" -> \"22A\" [label=\"L\"];",
+                "    \"22Z\" -> \"11A\" [label=\"R\"];",
+                "    \"XXX\" -> \"XXX\" [label=\"L\"];",
+                "    \"XXX\" -> \"XXX\" [label=\"R\"];",
+                "}",
+            ]
+        );
+        let dot = to_dot(&parse("L\n\na\"b\\ = (a\"b\\, a\"b\\)").unwrap());
+        assert_eq!(
+            dot.lines().nth(1),
+            Some(r#"    "a\"b\\" -> "a\"b\\" [label="L"];"#)
+        );
+    }
+
+    #[test]
+    fn bad_input() {
+        let err = parse("LRX\n\nAAA = (AAA, AAA)").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "day8 line 1 col 3: unknown instruction 'X'"
+        );
+        let err = parse("LR\n\nAAA = (AAA AAA)").unwrap_err();
+        assert_eq!(err.to_string(), "day8 line 3 col 8: expected ', '");
+        let err = parse("LR\n\nAAA = (AAA, ZZZ)").unwrap_err();
+        assert_eq!(err.to_string(), "day8 line 3 col 13: unknown node 'ZZZ'");
+        let err = parse("LR\n\nAAA = (AAA, AAA)\nAAA = (AAA, AAA)").unwrap_err();
+        assert_eq!(err.to_string(), "day8 line 4 col 1: duplicate node 'AAA'");
+        let err = parse("\n\nAAA = (AAA, AAA)").unwrap_err();
+        assert_eq!(err.to_string(), "day8 line 1 col 1: expected instructions");
     }
 }
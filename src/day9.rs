@@ -1,55 +1,236 @@
 use aoc_runner_derive::aoc;
 use aoc_runner_derive::aoc_generator;
 
+use crate::parse::{lines, ParseError};
+
+const DAY: u32 = 9;
+
+/// The values of a history, and the line they were read from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct History {
+    pub line: usize,
+    pub values: Vec<i64>,
+}
+
+/// Reads one history of values per line.
 #[aoc_generator(day9)]
-fn input_generator(input: &str) -> Vec<Vec<i32>> {
-    input
-        .lines()
+pub fn parse(input: &str) -> Result<Vec<History>, ParseError> {
+    lines(DAY, input)
         .map(|line| {
-            line.split_whitespace()
-                .map(|w| w.parse().unwrap())
-                .collect()
+            let values = line
+                .text
+                .split_whitespace()
+                .map(|w| line.parse(w))
+                .collect::<Result<_, _>>()?;
+            Ok(History {
+                line: line.number,
+                values,
+            })
         })
         .collect()
 }
 
-fn calc_diffs(line: &[i32]) -> Vec<i32> {
+fn calc_diffs(line: &[i128]) -> Option<Vec<i128>> {
     line.iter()
         .zip(line.iter().skip(1))
-        .map(|(a, b)| b - a)
+        .map(|(a, b)| b.checked_sub(*a))
         .collect()
 }
 
-#[aoc(day9, part1)]
-fn part1(lines: &[Vec<i32>]) -> i32 {
-    lines
-        .iter()
-        .map(|line| {
-            let mut edges = vec![];
-            let mut line = line.clone();
-            while line.iter().any(|&x| x != 0) {
-                edges.push(*line.last().unwrap());
-                line = calc_diffs(&line);
+/// The polynomial through a history's values, kept as the forward differences at its first value:
+/// the value `n` steps after the first is the sum of `diffs[k] * C(n, k)`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Polynomial {
+    pub diffs: Vec<i128>,
+}
+
+impl Polynomial {
+    /// Differences the history until nothing but zeros is left, or `None` if a difference
+    /// overflows.
+    pub fn fit(history: &[i64]) -> Option<Polynomial> {
+        let mut line = history.iter().map(|&x| x as i128).collect::<Vec<_>>();
+        let mut diffs = vec![];
+        while line.iter().any(|&x| x != 0) {
+            diffs.push(line[0]);
+            line = calc_diffs(&line)?;
+        }
+        Some(Polynomial { diffs })
+    }
+
+    pub fn degree(&self) -> usize {
+        self.diffs.len().saturating_sub(1)
+    }
+
+    /// The value `n` steps after the first one, where `n` may be negative, or `None` if it does
+    /// not fit.
+    pub fn value_at(&self, n: i128) -> Option<i128> {
+        let mut value = 0i128;
+        let mut binomial = 1i128;
+        for (k, &diff) in self.diffs.iter().enumerate() {
+            value = value.checked_add(diff.checked_mul(binomial)?)?;
+            // C(n, k + 1) = C(n, k) (n - k) / (k + 1), which divides exactly. It is only needed
+            // if there is a next term, and may not fit even when the value does.
+            if k + 1 < self.diffs.len() {
+                binomial = binomial.checked_mul(n - k as i128)? / (k as i128 + 1);
             }
-            edges.iter().rev().sum::<i32>()
-        })
-        .sum()
+        }
+        Some(value)
+    }
 }
 
-#[aoc(day9, part2)]
-fn part2(lines: &[Vec<i32>]) -> i32 {
-    lines
-        .iter()
-        .map(|line| {
-            let mut edges = vec![];
-            let mut line = line.clone();
-            while line.iter().any(|&x| x != 0) {
-                edges.push(*line.first().unwrap());
-                line = calc_diffs(&line);
+/// The value `steps` after the last one of the history.
+pub fn forward(history: &[i64], steps: i128) -> Option<i128> {
+    Polynomial::fit(history)?.value_at(history.len() as i128 - 1 + steps)
+}
+
+/// The value `steps` before the first one of the history.
+pub fn backward(history: &[i64], steps: i128) -> Option<i128> {
+    Polynomial::fit(history)?.value_at(-steps)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Classification {
+    /// Differencing reaches a row of zeros, so the history follows a polynomial of this degree.
+    Polynomial(usize),
+    /// Fewer than two values, which any polynomial fits.
+    InsufficientData,
+    /// Differencing runs out of values before reaching a row of zeros.
+    NotPolynomial,
+    /// A difference does not fit in an `i128`, so differencing could not finish.
+    Overflow,
+}
+
+pub fn classify(history: &[i64]) -> Classification {
+    if history.len() < 2 {
+        return Classification::InsufficientData;
+    }
+    match Polynomial::fit(history) {
+        // A row of zeros needs at least one value left to be seen.
+        Some(polynomial) if polynomial.diffs.len() < history.len() => {
+            Classification::Polynomial(polynomial.degree())
+        }
+        Some(_) => Classification::NotPolynomial,
+        None => Classification::Overflow,
+    }
+}
+
+/// The polynomial of every history, or an error for each history that has none, pointing at its
+/// line.
+pub fn validate(histories: &[History]) -> Result<Vec<Polynomial>, Vec<ParseError>> {
+    let mut polynomials = vec![];
+    let mut errors = vec![];
+    for history in histories {
+        let message = match classify(&history.values) {
+            Classification::Polynomial(_) => {
+                polynomials.extend(Polynomial::fit(&history.values));
+                continue;
+            }
+            Classification::InsufficientData => "too few values to extrapolate",
+            Classification::NotPolynomial => "history is not polynomial",
+            Classification::Overflow => "differences overflow",
+        };
+        errors.push(ParseError::at_line(DAY, history.line, message));
+    }
+    if errors.is_empty() {
+        Ok(polynomials)
+    } else {
+        Err(errors)
+    }
+}
+
+/// A least-squares polynomial, for histories too noisy to difference down to zeros. The
+/// coefficients are of the powers of the number of steps after the first value.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BestFit {
+    pub coefficients: Vec<f64>,
+}
+
+impl BestFit {
+    /// Fits a polynomial of the given degree, or `None` if there are not enough values to.
+    pub fn fit(history: &[i64], degree: usize) -> Option<BestFit> {
+        let n = degree + 1;
+        if history.len() < n {
+            return None;
+        }
+        // The normal equations, as an augmented matrix.
+        let mut rows = vec![vec![0.0; n + 1]; n];
+        for (x, &y) in history.iter().enumerate() {
+            let powers = (0..2 * n)
+                .map(|k| (x as f64).powi(k as i32))
+                .collect::<Vec<_>>();
+            for (i, row) in rows.iter_mut().enumerate() {
+                for j in 0..n {
+                    row[j] += powers[i + j];
+                }
+                row[n] += powers[i] * y as f64;
+            }
+        }
+        for col in 0..n {
+            let pivot =
+                (col..n).max_by(|&a, &b| rows[a][col].abs().total_cmp(&rows[b][col].abs()))?;
+            rows.swap(col, pivot);
+            if rows[col][col] == 0.0 {
+                return None;
             }
-            edges.iter().rev().fold(0, |acc, x| x - acc)
+            let pivot_row = rows[col].clone();
+            for (i, row) in rows.iter_mut().enumerate() {
+                if i != col {
+                    let factor = row[col] / pivot_row[col];
+                    for (x, p) in row[col..].iter_mut().zip(&pivot_row[col..]) {
+                        *x -= factor * p;
+                    }
+                }
+            }
+        }
+        let coefficients = rows
+            .iter()
+            .enumerate()
+            .map(|(i, row)| row[n] / row[i])
+            .collect();
+        Some(BestFit { coefficients })
+    }
+
+    pub fn value_at(&self, x: f64) -> f64 {
+        self.coefficients
+            .iter()
+            .rev()
+            .fold(0.0, |acc, c| acc * x + c)
+    }
+
+    /// The value `steps` after the last one of the history, rounded to the nearest integer.
+    pub fn forward(&self, history: &[i64], steps: i64) -> i64 {
+        self.value_at((history.len() as i64 - 1 + steps) as f64)
+            .round() as i64
+    }
+}
+
+/// Sums the value of each history's polynomial at the step `at` picks for it, failing at the
+/// first history that does not validate or whose value does not fit.
+fn extrapolate(lines: &[History], at: impl Fn(&History) -> i128) -> Result<i128, ParseError> {
+    let polynomials = validate(lines).map_err(|errors| errors[0].clone())?;
+    polynomials
+        .iter()
+        .zip(lines)
+        .try_fold(0i128, |sum, (polynomial, line)| {
+            let error = |message| ParseError::at_line(DAY, line.line, message);
+            let value = polynomial
+                .value_at(at(line))
+                .ok_or_else(|| error("extrapolated value overflows"))?;
+            sum.checked_add(value)
+                .ok_or_else(|| error("sum of extrapolated values overflows"))
         })
-        .sum()
+}
+
+/// Sums the next value of each history.
+#[aoc(day9, part1)]
+pub fn part1(lines: &[History]) -> Result<i128, ParseError> {
+    extrapolate(lines, |line| line.values.len() as i128)
+}
+
+/// Sums the value before the first one of each history.
+#[aoc(day9, part2)]
+pub fn part2(lines: &[History]) -> Result<i128, ParseError> {
+    extrapolate(lines, |_| -1)
 }
 
 #[cfg(test)]
@@ -62,11 +243,116 @@ mod test {
 
     #[test]
     fn sample1() {
-        assert_eq!(part1(&input_generator(INPUT)), 114)
+        assert_eq!(part1(&parse(INPUT).unwrap()), Ok(114))
     }
 
     #[test]
     fn sample2() {
-        assert_eq!(part2(&input_generator(INPUT)), 2)
+        assert_eq!(part2(&parse(INPUT).unwrap()), Ok(2))
+    }
+
+    #[test]
+    fn extrapolate() {
+        let histories = parse(INPUT).unwrap();
+        let degrees = histories
+            .iter()
+            .map(|history| Polynomial::fit(&history.values).unwrap().degree())
+            .collect::<Vec<_>>();
+        assert_eq!(degrees, vec![1, 2, 3]);
+        assert_eq!(forward(&histories[0].values, 3), Some(24));
+        assert_eq!(backward(&histories[0].values, 2), Some(-6));
+        assert_eq!(backward(&histories[1].values, 3), Some(1));
+        assert_eq!(forward(&histories[2].values, 0), Some(45));
+        // Cubes far past the range of i64.
+        let cubes = (0..6).map(|n: i64| n.pow(3)).collect::<Vec<_>>();
+        assert_eq!(
+            forward(&cubes, 1_000_000_000),
+            Some(1_000_000_005i128.pow(3))
+        );
+        assert_eq!(backward(&cubes, 7), Some(-343));
+        assert_eq!(Polynomial::fit(&[0, 0, 0]).unwrap().degree(), 0);
+        assert_eq!(forward(&[i64::MAX, i64::MIN], 1i128 << 100), None);
+        // C(n, 2) overflows, but a line never needs it.
+        assert_eq!(forward(&[1, 2], 1i128 << 100), Some((1i128 << 100) + 2));
+    }
+
+    #[test]
+    fn classification() {
+        let classes = parse(INPUT)
+            .unwrap()
+            .iter()
+            .map(|history| classify(&history.values))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            classes,
+            (1..=3).map(Classification::Polynomial).collect::<Vec<_>>()
+        );
+        assert_eq!(classify(&[3, 3]), Classification::Polynomial(0));
+        assert_eq!(classify(&[0, 0, 0]), Classification::Polynomial(0));
+        assert_eq!(classify(&[5]), Classification::InsufficientData);
+        assert_eq!(classify(&[1, 2, 4, 8]), Classification::NotPolynomial);
+        // Alternating extremes double the differences on every row.
+        let extremes = (0..70)
+            .map(|i| if i % 2 == 0 { i64::MAX } else { i64::MIN })
+            .collect::<Vec<_>>();
+        assert_eq!(classify(&extremes), Classification::Overflow);
+        // The differences fit, but the next value does not.
+        let flipped = (0..66)
+            .map(|i| {
+                if (i % 2 == 0) == (i < 33) {
+                    1 << 40
+                } else {
+                    -1 << 40
+                }
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(classify(&flipped), Classification::Polynomial(64));
+        let line = flipped.iter().map(|v| v.to_string()).collect::<Vec<_>>();
+        let histories = parse(&format!("0 1 2\n{}", line.join(" "))).unwrap();
+        assert_eq!(
+            part1(&histories).unwrap_err().to_string(),
+            "day9 line 2 col 1: extrapolated value overflows"
+        );
+
+        let mut histories = parse("0 3 6 9\n1 2 4 8\n7\n1 3 6 10 15").unwrap();
+        let errors = validate(&histories)
+            .unwrap_err()
+            .iter()
+            .map(|err| err.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            errors,
+            vec![
+                "day9 line 2 col 1: history is not polynomial",
+                "day9 line 3 col 1: too few values to extrapolate",
+            ]
+        );
+        // Errors point at the line a history was read from, not its place in the list.
+        histories.remove(0);
+        assert_eq!(
+            validate(&histories).unwrap_err()[0].to_string(),
+            "day9 line 2 col 1: history is not polynomial"
+        );
+        assert_eq!(
+            part1(&histories),
+            Err(validate(&histories).unwrap_err()[0].clone())
+        );
+    }
+
+    #[test]
+    fn best_fit() {
+        let noisy = [0, 2, 3, 6, 8, 10];
+        let fit = BestFit::fit(&noisy, 1).unwrap();
+        assert!((fit.value_at(6.0) - 11.933).abs() < 0.001);
+        assert_eq!(fit.forward(&noisy, 1), 12);
+        let exact = BestFit::fit(&[1, 3, 6, 10, 15, 21], 2).unwrap();
+        assert!((exact.value_at(6.0) - 28.0).abs() < 1e-9);
+        assert_eq!(BestFit::fit(&[1, 2], 2), None);
+    }
+
+    #[test]
+    fn bad_number() {
+        let err = parse("0 3 6\n1 3 - 10").unwrap_err();
+        assert_eq!((err.line, err.column, err.snippet.as_str()), (2, 5, "-"));
     }
 }
@@ -0,0 +1,287 @@
+use std::ops::Index;
+use std::ops::IndexMut;
+
+use crate::parse::{lines, Line, ParseError};
+
+/// A position in a grid, as (x, y) with y growing downwards.
+pub type Pos = (usize, usize);
+pub type Delta = (isize, isize);
+
+pub const UP: Delta = (0, -1);
+pub const DOWN: Delta = (0, 1);
+pub const LEFT: Delta = (-1, 0);
+pub const RIGHT: Delta = (1, 0);
+
+pub const NEIGHBOURS4: [Delta; 4] = [UP, DOWN, LEFT, RIGHT];
+pub const NEIGHBOURS8: [Delta; 8] = [
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+    (-1, 0),
+    (1, 0),
+    (-1, 1),
+    (0, 1),
+    (1, 1),
+];
+
+/// A rectangular grid of cells, stored row by row in a single vector.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Grid<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>,
+}
+
+impl<T> Grid<T> {
+    pub fn new(width: usize, height: usize, cells: Vec<T>) -> Grid<T> {
+        assert_eq!(cells.len(), width * height);
+        Grid {
+            width,
+            height,
+            cells,
+        }
+    }
+
+    pub fn filled(width: usize, height: usize, value: T) -> Grid<T>
+    where
+        T: Clone,
+    {
+        Grid::new(width, height, vec![value; width * height])
+    }
+
+    /// Parses one cell per character, failing on characters `cell` does not accept and on rows
+    /// of differing lengths.
+    pub fn parse<F: FnMut(char) -> Option<T>>(
+        day: u32,
+        input: &str,
+        what: &str,
+        cell: F,
+    ) -> Result<Grid<T>, ParseError> {
+        let mut lines = lines(day, input);
+        let first = lines.expect("grid")?;
+        Grid::from_lines(std::iter::once(first).chain(lines), what, cell)
+    }
+
+    pub fn from_lines<'a, I: IntoIterator<Item = Line<'a>>, F: FnMut(char) -> Option<T>>(
+        lines: I,
+        what: &str,
+        mut cell: F,
+    ) -> Result<Grid<T>, ParseError> {
+        let mut width = None;
+        let mut height = 0;
+        let mut cells = Vec::new();
+        for line in lines {
+            let row_width = line.text.chars().count();
+            if let Some(width) = width.filter(|&w| w != row_width) {
+                return Err(line.error(format!(
+                    "expected row of length {}, found {}",
+                    width, row_width
+                )));
+            }
+            width = Some(row_width);
+            for (x, c) in line.text.chars().enumerate() {
+                cells.push(cell(c).ok_or_else(|| line.unknown_char(x, c, what))?);
+            }
+            height += 1;
+        }
+        Ok(Grid::new(width.unwrap_or(0), height, cells))
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn contains(&self, (x, y): Pos) -> bool {
+        x < self.width && y < self.height
+    }
+
+    pub fn get(&self, pos: Pos) -> Option<&T> {
+        self.contains(pos).then(|| &self[pos])
+    }
+
+    pub fn get_mut(&mut self, pos: Pos) -> Option<&mut T> {
+        if self.contains(pos) {
+            Some(&mut self[pos])
+        } else {
+            None
+        }
+    }
+
+    /// The position one `delta` away from `pos`, if it is inside the grid.
+    pub fn offset(&self, (x, y): Pos, (dx, dy): Delta) -> Option<Pos> {
+        let pos = (x.checked_add_signed(dx)?, y.checked_add_signed(dy)?);
+        self.contains(pos).then_some(pos)
+    }
+
+    pub fn neighbours4(&self, pos: Pos) -> impl Iterator<Item = Pos> + '_ {
+        NEIGHBOURS4
+            .iter()
+            .filter_map(move |&delta| self.offset(pos, delta))
+    }
+
+    pub fn neighbours8(&self, pos: Pos) -> impl Iterator<Item = Pos> + '_ {
+        NEIGHBOURS8
+            .iter()
+            .filter_map(move |&delta| self.offset(pos, delta))
+    }
+
+    pub fn positions(&self) -> impl Iterator<Item = Pos> {
+        let width = self.width;
+        (0..self.cells.len()).map(move |i| (i % width, i / width))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Pos, &T)> {
+        self.positions().zip(self.cells.iter())
+    }
+
+    pub fn position<P: FnMut(&T) -> bool>(&self, predicate: P) -> Option<Pos> {
+        let i = self.cells.iter().position(predicate)?;
+        Some((i % self.width, i / self.width))
+    }
+
+    pub fn row(&self, y: usize) -> &[T] {
+        &self.cells[y * self.width..(y + 1) * self.width]
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+        // chunks() rejects a chunk size of zero, which a grid of empty rows would give it.
+        (0..self.height).map(|y| self.row(y))
+    }
+
+    pub fn column(&self, x: usize) -> impl DoubleEndedIterator<Item = &T> {
+        // step_by() panics on a zero width, and x past the edge would wrap into the next row.
+        assert!(x < self.width, "column {} out of bounds", x);
+        self.cells[x..].iter().step_by(self.width)
+    }
+
+    pub fn columns(&self) -> impl Iterator<Item = impl Iterator<Item = &T>> {
+        (0..self.width).map(|x| self.column(x))
+    }
+
+    pub fn map<U, F: FnMut(&T) -> U>(&self, f: F) -> Grid<U> {
+        Grid::new(self.width, self.height, self.cells.iter().map(f).collect())
+    }
+
+    /// Renders the grid as lines of text, one character per cell.
+    pub fn render<F: Fn(&T) -> char>(&self, f: F) -> String {
+        self.rows()
+            .map(|row| row.iter().map(&f).chain(['\n']).collect::<String>())
+            .collect()
+    }
+}
+
+impl<T: Clone> Grid<T> {
+    /// Mirrors the grid along its main diagonal, so rows become columns.
+    pub fn transpose(&self) -> Grid<T> {
+        Grid::new(
+            self.height,
+            self.width,
+            (0..self.width)
+                .flat_map(|x| self.column(x).cloned())
+                .collect(),
+        )
+    }
+
+    pub fn rotate_clockwise(&self) -> Grid<T> {
+        Grid::new(
+            self.height,
+            self.width,
+            (0..self.width)
+                .flat_map(|x| self.column(x).rev().cloned())
+                .collect(),
+        )
+    }
+
+    pub fn rotate_counterclockwise(&self) -> Grid<T> {
+        Grid::new(
+            self.height,
+            self.width,
+            (0..self.width)
+                .rev()
+                .flat_map(|x| self.column(x).cloned())
+                .collect(),
+        )
+    }
+}
+
+impl<T> Index<Pos> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, (x, y): Pos) -> &T {
+        assert!(x < self.width, "x {} out of bounds {}", x, self.width);
+        &self.cells[y * self.width + x]
+    }
+}
+
+impl<T> IndexMut<Pos> for Grid<T> {
+    fn index_mut(&mut self, (x, y): Pos) -> &mut T {
+        assert!(x < self.width, "x {} out of bounds {}", x, self.width);
+        &mut self.cells[y * self.width + x]
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn sample() -> Grid<char> {
+        Grid::parse(0, "abc\ndef", "cell", Some).unwrap()
+    }
+
+    #[test]
+    fn views() {
+        let grid = sample();
+        assert_eq!((grid.width(), grid.height()), (3, 2));
+        assert_eq!(grid[(2, 1)], 'f');
+        assert_eq!(grid.get((3, 0)), None);
+        assert_eq!(grid.row(1), ['d', 'e', 'f']);
+        assert_eq!(grid.column(1).collect::<String>(), "be");
+        assert_eq!(grid.position(|&c| c == 'e'), Some((1, 1)));
+        assert_eq!(
+            grid.neighbours4((0, 0)).collect::<Vec<_>>(),
+            vec![(0, 1), (1, 0)]
+        );
+        assert_eq!(grid.neighbours8((1, 0)).count(), 5);
+    }
+
+    #[test]
+    #[should_panic(expected = "column 3 out of bounds")]
+    fn column_out_of_bounds() {
+        let _ = sample().column(3);
+    }
+
+    #[test]
+    fn transformations() {
+        let grid = sample();
+        let render = |grid: &Grid<char>| grid.render(|&c| c);
+        assert_eq!(render(&grid.transpose()), "ad\nbe\ncf\n");
+        assert_eq!(render(&grid.rotate_clockwise()), "da\neb\nfc\n");
+        assert_eq!(render(&grid.rotate_counterclockwise()), "cf\nbe\nad\n");
+        let mut rotated = grid.clone();
+        for _ in 0..4 {
+            rotated = rotated.rotate_clockwise();
+        }
+        assert_eq!(rotated, grid);
+    }
+
+    #[test]
+    fn bad_grid() {
+        let digit = |c: char| c.to_digit(10);
+        let err = Grid::parse(9, "123\n4x6", "digit", digit).unwrap_err();
+        assert_eq!(err.to_string(), "day9 line 2 col 2: unknown digit 'x'");
+        let err = Grid::parse(9, "123\n45", "digit", digit).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "day9 line 2 col 1: expected row of length 3, found 2"
+        );
+        let err = Grid::parse(9, "", "digit", digit).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "day9 line 1 col 1: unexpected end of input, expected grid"
+        );
+    }
+}
@@ -1,3 +1,8 @@
+//! Solutions to Advent of Code 2023.
+//!
+//! Each `dayN` module exposes its parsed input type, a `parse` function that reports malformed
+//! input as a [`ParseError`], and `part1`/`part2` solvers taking the parsed input.
+
 #![feature(let_chains)]
 #![feature(iter_intersperse)]
 #![feature(ascii_char)]
@@ -19,5 +24,25 @@ pub mod day6;
 pub mod day7;
 pub mod day8;
 pub mod day9;
+pub mod grid;
+pub mod parse;
+
+pub use day1::CalibrationLine;
+pub use day10::Maze;
+pub use day11::Image;
+pub use day12::Record;
+pub use day13::Pattern;
+pub use day14::Platform;
+pub use day15::Step;
+pub use day2::Game;
+pub use day3::Schematic;
+pub use day4::Card;
+pub use day5::Almanac;
+pub use day6::Races;
+pub use day7::{Hand, RawHand};
+pub use day8::Network;
+pub use day9::History;
+pub use grid::Grid;
+pub use parse::ParseError;
 
 aoc_lib! { year = 2023 }
@@ -0,0 +1,208 @@
+use std::error::Error;
+use std::fmt;
+use std::fmt::Display;
+use std::str::FromStr;
+
+/// Malformed puzzle input, located by day, line and column.
+#[derive(Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub day: u32,
+    pub line: usize,
+    pub column: usize,
+    pub snippet: String,
+    pub message: String,
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "day{} line {} col {}: {}",
+            self.day, self.line, self.column, self.message
+        )
+    }
+}
+
+/// The runner reports a failed generator by its `Debug` form, so keep it the one-line message.
+impl fmt::Debug for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        Display::fmt(self, f)
+    }
+}
+
+impl Error for ParseError {}
+
+impl ParseError {
+    pub fn at_line<M: Into<String>>(day: u32, line: usize, message: M) -> ParseError {
+        ParseError {
+            day,
+            line,
+            column: 1,
+            snippet: String::new(),
+            message: message.into(),
+        }
+    }
+}
+
+pub type Result<T> = std::result::Result<T, ParseError>;
+
+/// A single line of puzzle input, remembering where it came from so that errors can point at it.
+#[derive(Debug, Clone, Copy)]
+pub struct Line<'a> {
+    pub day: u32,
+    pub number: usize,
+    pub text: &'a str,
+}
+
+impl<'a> Line<'a> {
+    /// Column (1-based, in characters) of `part`, which should be a slice of this line.
+    fn column_of(&self, part: &str) -> usize {
+        let offset = (part.as_ptr() as usize).wrapping_sub(self.text.as_ptr() as usize);
+        match self.text.get(..offset) {
+            Some(before) => before.chars().count() + 1,
+            None => 1,
+        }
+    }
+
+    pub fn error_at<M: Into<String>>(&self, part: &str, message: M) -> ParseError {
+        ParseError {
+            day: self.day,
+            line: self.number,
+            column: self.column_of(part),
+            snippet: part.to_string(),
+            message: message.into(),
+        }
+    }
+
+    pub fn error_at_column<M: Into<String>>(&self, column: usize, message: M) -> ParseError {
+        ParseError {
+            day: self.day,
+            line: self.number,
+            column: column + 1,
+            snippet: self.text.chars().skip(column).take(1).collect(),
+            message: message.into(),
+        }
+    }
+
+    pub fn error<M: Into<String>>(&self, message: M) -> ParseError {
+        self.error_at(self.text, message)
+    }
+
+    /// The empty slice just past the end of the line, for reporting missing fields.
+    pub fn end(&self) -> &'a str {
+        &self.text[self.text.len()..]
+    }
+
+    pub fn split_once(&self, part: &'a str, delimiter: &str) -> Result<(&'a str, &'a str)> {
+        part.split_once(delimiter)
+            .ok_or_else(|| self.error_at(part, format!("expected '{}'", delimiter)))
+    }
+
+    pub fn parse<T: FromStr>(&self, part: &'a str) -> Result<T>
+    where
+        T::Err: Display,
+    {
+        part.parse()
+            .map_err(|err| self.error_at(part, format!("cannot parse '{}': {}", part, err)))
+    }
+
+    pub fn next<I: Iterator<Item = &'a str>>(&self, parts: &mut I, what: &str) -> Result<&'a str> {
+        parts
+            .next()
+            .ok_or_else(|| self.error_at(self.end(), format!("missing {}", what)))
+    }
+
+    pub fn unknown_char(&self, column: usize, c: char, what: &str) -> ParseError {
+        self.error_at_column(column, format!("unknown {} '{}'", what, c.escape_debug()))
+    }
+}
+
+pub struct Lines<'a> {
+    day: u32,
+    number: usize,
+    inner: std::str::Lines<'a>,
+}
+
+impl<'a> Iterator for Lines<'a> {
+    type Item = Line<'a>;
+
+    fn next(&mut self) -> Option<Line<'a>> {
+        let text = self.inner.next()?;
+        self.number += 1;
+        Some(Line {
+            day: self.day,
+            number: self.number,
+            text,
+        })
+    }
+}
+
+impl<'a> Lines<'a> {
+    pub fn expect(&mut self, what: &str) -> Result<Line<'a>> {
+        let number = self.number + 1;
+        self.next().ok_or_else(|| {
+            ParseError::at_line(
+                self.day,
+                number,
+                format!("unexpected end of input, expected {}", what),
+            )
+        })
+    }
+
+    pub fn expect_blank(&mut self) -> Result<()> {
+        let line = self.expect("blank line")?;
+        if line.text.is_empty() {
+            Ok(())
+        } else {
+            Err(line.error("expected blank line"))
+        }
+    }
+}
+
+pub fn lines(day: u32, input: &str) -> Lines<'_> {
+    Lines {
+        day,
+        number: 0,
+        inner: input.lines(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn error_position() {
+        let line = lines(7, "32T3K 765\nQQXJA 483").nth(1).unwrap();
+        let (cards, _) = line.split_once(line.text, " ").unwrap();
+        let err = line.unknown_char(2, cards.chars().nth(2).unwrap(), "card");
+        assert_eq!(err.to_string(), "day7 line 2 col 3: unknown card 'X'");
+        assert_eq!(err.snippet, "X");
+        assert_eq!(format!("{:?}", err), "day7 line 2 col 3: unknown card 'X'");
+    }
+
+    #[test]
+    fn missing_field() {
+        let line = lines(2, "Game 1").next().unwrap();
+        let err = line.split_once(line.text, ": ").unwrap_err();
+        assert_eq!((err.line, err.column), (1, 1));
+        let mut words = line.text.split(' ');
+        words.next();
+        words.next();
+        let err = line.next(&mut words, "colour").unwrap_err();
+        assert_eq!(err.to_string(), "day2 line 1 col 7: missing colour");
+        assert_eq!(line.parse::<u32>(&line.text[5..]), Ok(1));
+        assert_eq!(line.parse::<u32>(&line.text[..4]).unwrap_err().column, 1);
+    }
+
+    #[test]
+    fn truncated_input() {
+        let mut lines = lines(6, "Time: 7 15 30");
+        lines.expect("times").unwrap();
+        let err = lines.expect("distances").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "day6 line 2 col 1: unexpected end of input, expected distances"
+        );
+    }
+}
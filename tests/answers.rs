@@ -0,0 +1,144 @@
+use aoc2023::*;
+
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::fs;
+
+type Key = (u32, u32, Option<String>);
+type Solver = fn(&str) -> Result<String, ParseError>;
+
+trait Answer {
+    fn answer(self) -> Result<String, ParseError>;
+}
+
+macro_rules! display_answer {
+    ($($t:ty),*) => {
+        $(impl Answer for $t {
+            fn answer(self) -> Result<String, ParseError> {
+                Ok(self.to_string())
+            }
+        })*
+    };
+}
+
+display_answer!(i32, i64, i128, u32, u64, usize, String);
+
+impl<T: Answer> Answer for Result<T, ParseError> {
+    fn answer(self) -> Result<String, ParseError> {
+        self?.answer()
+    }
+}
+
+impl<T: Answer> Answer for Result<T, day5::PathError> {
+    fn answer(self) -> Result<String, ParseError> {
+        self.map_or_else(|err| Ok(err.to_string()), T::answer)
+    }
+}
+
+impl<T: Answer> Answer for Option<T> {
+    fn answer(self) -> Result<String, ParseError> {
+        match self {
+            Some(answer) => answer.answer(),
+            None => Ok("None".to_string()),
+        }
+    }
+}
+
+macro_rules! solvers {
+    ($($day:ident $n:literal { $($part:literal $($variant:literal)? => $solver:ident),* })*) => {
+        vec![$($((
+            ($n, $part, None$(.or(Some($variant.to_string())))?),
+            (|input: &str| $day::$solver(&$day::parse(input)?).answer()) as Solver,
+        )),*),*]
+    };
+}
+
+fn solvers() -> Vec<(Key, Solver)> {
+    solvers! {
+        day1 1 { 1 => part1, 2 => part2 }
+        day2 2 { 1 => part1, 2 => part2 }
+        day3 3 { 1 => part1, 2 => part2 }
+        day4 4 { 1 => part1, 2 => part2 }
+        day5 5 { 1 => part1, 2 => part2, 2 "inverse" => part2_inverse }
+        day6 6 { 1 => part1, 2 => part2 }
+        day7 7 { 1 => part1, 2 => part2 }
+        day8 8 { 1 => part1, 2 "simplified" => part2_simplified, 2 "generic" => part2_generic }
+        day9 9 { 1 => part1, 2 => part2 }
+        day10 10 { 1 => part1, 2 => part2, 2 "shoelace" => part2_shoelace, 2 "squeeze" => part2_squeeze }
+        day11 11 { 1 => part1, 2 => part2 }
+        day12 12 { 1 => part1, 2 => part2 }
+        day13 13 { 1 => part1, 2 => part2 }
+        day14 14 { 1 => part1, 2 => part2 }
+        day15 15 { 1 => part1, 2 => part2 }
+    }
+}
+
+fn parse_key(day: &str, part: &str, variant: Option<&str>) -> Key {
+    (
+        day.trim_start_matches("day").parse().unwrap(),
+        part.trim_start_matches("part").parse().unwrap(),
+        variant.map(|v| v.to_string()),
+    )
+}
+
+fn answers() -> BTreeMap<Key, String> {
+    fs::read_to_string("input/2023/answers.txt")
+        .unwrap()
+        .lines()
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| {
+            let words = line.split_whitespace().collect::<Vec<_>>();
+            match words[..] {
+                [day, part, answer] => (parse_key(day, part, None), answer.to_string()),
+                [day, part, variant, answer] => {
+                    (parse_key(day, part, Some(variant)), answer.to_string())
+                }
+                _ => panic!("Malformed answer: {}", line),
+            }
+        })
+        .collect()
+}
+
+/// Every solver registered with `#[aoc(...)]` in the library sources.
+fn registered() -> BTreeSet<Key> {
+    let mut keys = BTreeSet::new();
+    for entry in fs::read_dir("src").unwrap() {
+        let source = fs::read_to_string(entry.unwrap().path()).unwrap();
+        for line in source.lines().map(str::trim) {
+            if let Some(args) = line
+                .strip_prefix("#[aoc(")
+                .and_then(|rest| rest.strip_suffix(")]"))
+            {
+                let args = args.split(", ").collect::<Vec<_>>();
+                keys.insert(parse_key(args[0], args[1], args.get(2).copied()));
+            }
+        }
+    }
+    keys
+}
+
+#[test]
+fn every_solver_has_an_answer() {
+    let solvers = solvers().into_iter().map(|(key, _)| key).collect();
+    assert_eq!(registered(), solvers);
+    assert_eq!(answers().into_keys().collect::<BTreeSet<_>>(), solvers);
+}
+
+#[test]
+fn answers_match() {
+    let answers = answers();
+    let mismatches = solvers()
+        .into_iter()
+        .filter_map(|(key, solver)| {
+            let input = fs::read_to_string(format!("input/2023/day{}.txt", key.0)).unwrap();
+            let actual = match solver(&input) {
+                Ok(answer) => answer,
+                Err(err) => err.to_string(),
+            };
+            let expected = &answers[&key];
+            (&actual != expected)
+                .then(|| format!("{:?}: expected {}, got {}", key, expected, actual))
+        })
+        .collect::<Vec<_>>();
+    assert!(mismatches.is_empty(), "{}", mismatches.join("\n"));
+}